@@ -4,58 +4,100 @@
 //! Replaces the Python download_models.py script.
 
 use indicatif::{ProgressBar, ProgressStyle};
+use music_tool::models::{find_model, ModelInfo, MODELS};
 use sha2::{Sha256, Digest};
-use std::fs::{self, File};
+use std::fs::{self, File, OpenOptions};
 use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-/// Model information
-struct ModelInfo {
-    name: &'static str,
-    url: &'static str,
-    sha256: &'static str,
-    size_mb: u64,
+/// Path of the sidecar file recording which URL a partial download at `dest`
+/// came from, so a later attempt from a *different* mirror doesn't resume
+/// onto bytes it didn't write (see [`download_with_progress`]).
+fn partial_source_marker(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_os_string();
+    name.push(".partial-source");
+    PathBuf::from(name)
 }
 
-/// Available Demucs ONNX models
-/// The htdemucs models need to be converted from PyTorch to ONNX format
-const MODELS: &[ModelInfo] = &[
-    ModelInfo {
-        name: "htdemucs_6s",
-        // UVR (Ultimate Vocal Remover) provides ONNX models for various separators
-        // This is the 6-stem Demucs model converted to ONNX
-        url: "https://github.com/facefusion/facefusion-assets/releases/download/models-3.0.0/demucs_htdemucs_6s.onnx",
-        sha256: "", // Empty means skip verification (model may vary)
-        size_mb: 85,
-    },
-];
+/// Remove `dest` and its partial-source marker, if present.
+fn remove_download(dest: &Path) -> std::io::Result<()> {
+    if dest.exists() {
+        fs::remove_file(dest)?;
+    }
+    let marker = partial_source_marker(dest);
+    if marker.exists() {
+        fs::remove_file(marker)?;
+    }
+    Ok(())
+}
 
+/// Download (or resume) `url` into `dest`, appending to any partial file
+/// already there via an HTTP range request. Falls back to a fresh download
+/// if the server doesn't honor the range (responds 200 instead of 206).
+///
+/// A partial file is only resumed if it was itself written by this same
+/// `url`: mirrors aren't guaranteed to serve byte-identical content, so
+/// resuming mirror B's response onto bytes mirror A already wrote can splice
+/// together a corrupt file that `sha256` won't catch when it's empty. The
+/// source URL is tracked in a `<dest>.partial-source` sidecar file; a
+/// mismatch (or a missing marker) discards the partial data and starts over.
 fn download_with_progress(url: &str, dest: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
-    println!("Downloading from: {}", url);
-    
-    let response = reqwest::blocking::Client::builder()
+    let marker = partial_source_marker(dest);
+    let mut existing_len = fs::metadata(dest).map(|m| m.len()).unwrap_or(0);
+
+    if existing_len > 0 {
+        let same_source = fs::read_to_string(&marker).map(|s| s == url).unwrap_or(false);
+        if !same_source {
+            println!("  Partial download came from a different mirror, restarting from scratch");
+            remove_download(dest)?;
+            existing_len = 0;
+        }
+    }
+
+    if existing_len > 0 {
+        println!("Resuming from byte {} at: {}", existing_len, url);
+    } else {
+        println!("Downloading from: {}", url);
+    }
+
+    let client = reqwest::blocking::Client::builder()
         .timeout(std::time::Duration::from_secs(3600)) // 1 hour timeout for large files
-        .build()?
-        .get(url)
-        .send()?;
-    
+        .build()?;
+
+    let mut request = client.get(url);
+    if existing_len > 0 {
+        request = request.header("Range", format!("bytes={}-", existing_len));
+    }
+    let response = request.send()?;
+
     if !response.status().is_success() {
         return Err(format!("HTTP error: {}", response.status()).into());
     }
-    
-    let total_size = response.content_length().unwrap_or(0);
-    
+
+    let resumed = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let (mut file, mut downloaded) = if resumed {
+        let file = OpenOptions::new().append(true).open(dest)?;
+        (file, existing_len)
+    } else {
+        // Server ignored the Range header (or this is the first attempt):
+        // start the file over from scratch.
+        (File::create(dest)?, 0)
+    };
+    // Record which URL owns the bytes now in `dest`, so a future attempt
+    // from a different mirror knows not to resume onto them.
+    fs::write(&marker, url)?;
+
+    let total_size = response.content_length().unwrap_or(0) + downloaded;
+
     let pb = ProgressBar::new(total_size);
     pb.set_style(ProgressStyle::default_bar()
         .template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")?
         .progress_chars("#>-"));
-    
-    let mut file = File::create(dest)?;
-    let mut downloaded: u64 = 0;
-    
+    pb.set_position(downloaded);
+
     let mut reader = response;
     let mut buffer = [0u8; 8192];
-    
+
     loop {
         let bytes_read = reader.read(&mut buffer)?;
         if bytes_read == 0 {
@@ -65,11 +107,30 @@ fn download_with_progress(url: &str, dest: &PathBuf) -> Result<(), Box<dyn std::
         downloaded += bytes_read as u64;
         pb.set_position(downloaded);
     }
-    
+
     pb.finish_with_message("Download complete");
+    // The file is whole now, so the partial-source marker no longer applies.
+    let _ = fs::remove_file(&marker);
     Ok(())
 }
 
+/// Try each mirror in order until one succeeds. A partial download is only
+/// resumed by the mirror that wrote it; switching to another mirror starts
+/// the file over (see [`download_with_progress`]).
+fn download_with_mirrors(mirrors: &[&str], dest: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+    let mut last_err = None;
+    for url in mirrors {
+        match download_with_progress(url, dest) {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                eprintln!("  Mirror failed ({}): {}", url, e);
+                last_err = Some(e);
+            }
+        }
+    }
+    Err(last_err.unwrap_or_else(|| "No mirrors configured".into()))
+}
+
 fn verify_checksum(path: &PathBuf, expected: &str) -> Result<bool, Box<dyn std::error::Error>> {
     if expected.is_empty() {
         println!("  Skipping checksum verification (no hash provided)");
@@ -106,34 +167,37 @@ fn verify_checksum(path: &PathBuf, expected: &str) -> Result<bool, Box<dyn std::
 
 fn download_model(model: &ModelInfo, output_dir: &PathBuf) -> Result<PathBuf, Box<dyn std::error::Error>> {
     let model_path = output_dir.join(format!("{}.onnx", model.name));
-    
-    // Check if already exists and valid
+
+    // Check if already exists and valid. Verifying here (rather than after
+    // every run) means a checksum mismatch on a previously-completed file
+    // forces a clean re-download instead of silently resuming a corrupt one.
     if model_path.exists() {
         println!("Model {} already exists at {}", model.name, model_path.display());
         if verify_checksum(&model_path, model.sha256)? {
             return Ok(model_path);
         }
         println!("Checksum failed, re-downloading...");
-        fs::remove_file(&model_path)?;
+        remove_download(&model_path)?;
     }
-    
+
     println!("\nDownloading model: {} (~{}MB)", model.name, model.size_mb);
-    
-    // Try to download from URL
-    match download_with_progress(model.url, &model_path) {
-        Ok(_) => {
-            // Verify checksum
+
+    // Try each mirror, resuming a partial download (from an interrupted
+    // previous attempt) via HTTP range requests.
+    match download_with_mirrors(model.mirrors, &model_path) {
+        Ok(()) => {
+            // Only verify once the download has actually finished - running
+            // the full-file checksum against a partial download would
+            // always fail and defeat resuming.
             if !verify_checksum(&model_path, model.sha256)? {
-                fs::remove_file(&model_path)?;
+                remove_download(&model_path)?;
                 return Err("Checksum verification failed".into());
             }
             Ok(model_path)
         }
         Err(e) => {
-            // Clean up partial download
-            if model_path.exists() {
-                fs::remove_file(&model_path).ok();
-            }
+            // Leave the partial file in place so the next invocation can
+            // resume instead of starting over.
             Err(e)
         }
     }
@@ -242,7 +306,7 @@ fn main() {
     }
     
     // Find the requested model
-    let model = MODELS.iter().find(|m| m.name == model_name);
+    let model = find_model(model_name);
     let model = match model {
         Some(m) => m,
         None => {
@@ -0,0 +1,189 @@
+//! Channel layout conversion (downmix/upmix) for feeding audio models that
+//! expect a fixed channel count and for writing stems back out in the
+//! layout the caller originally supplied.
+
+use anyhow::{Result, anyhow};
+use ndarray::{Array2, ArrayView2};
+
+/// A single channel-conversion operation.
+///
+/// `src_ch` and `dst_ch` below refer to the channel counts of the input and
+/// output of the operation, not any particular layout name, so the same
+/// operation can be reused for arbitrary channel counts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelOp {
+    /// Input and output channel layout are identical; samples pass through
+    /// unchanged.
+    Passthrough,
+    /// Permute channels by index: output channel `j` is input channel
+    /// `indices[j]`.
+    Reorder(Vec<usize>),
+    /// General linear remix: a flattened `dst_ch x src_ch` coefficient
+    /// matrix where `dst[j] = sum_i src[i] * coef[j * src_ch + i]`.
+    Remix(Vec<f32>),
+    /// Broadcast a single source channel to every destination channel.
+    DupMono,
+}
+
+/// Apply a channel-conversion operation to `input` (channels x samples),
+/// producing `dst_channels` output channels.
+pub fn apply_channel_op(
+    input: ArrayView2<f32>,
+    op: &ChannelOp,
+    dst_channels: usize,
+) -> Result<Array2<f32>> {
+    let (src_channels, samples) = (input.shape()[0], input.shape()[1]);
+
+    match op {
+        ChannelOp::Passthrough => {
+            if src_channels != dst_channels {
+                return Err(anyhow!(
+                    "Passthrough requires matching channel counts (src {} != dst {})",
+                    src_channels,
+                    dst_channels
+                ));
+            }
+            Ok(input.to_owned())
+        }
+        ChannelOp::Reorder(indices) => {
+            if indices.len() != dst_channels {
+                return Err(anyhow!(
+                    "Reorder index count {} does not match dst_channels {}",
+                    indices.len(),
+                    dst_channels
+                ));
+            }
+            let mut output = Array2::<f32>::zeros((dst_channels, samples));
+            for (dst_ch, &src_ch) in indices.iter().enumerate() {
+                if src_ch >= src_channels {
+                    return Err(anyhow!(
+                        "Reorder index {} out of range for {} source channels",
+                        src_ch,
+                        src_channels
+                    ));
+                }
+                output.row_mut(dst_ch).assign(&input.row(src_ch));
+            }
+            Ok(output)
+        }
+        ChannelOp::Remix(coef) => {
+            if coef.len() != dst_channels * src_channels {
+                return Err(anyhow!(
+                    "Remix matrix has {} coefficients, expected dst_ch * src_ch = {}",
+                    coef.len(),
+                    dst_channels * src_channels
+                ));
+            }
+            let mut output = Array2::<f32>::zeros((dst_channels, samples));
+            for dst_ch in 0..dst_channels {
+                for src_ch in 0..src_channels {
+                    let c = coef[dst_ch * src_channels + src_ch];
+                    if c == 0.0 {
+                        continue;
+                    }
+                    let src_row = input.row(src_ch);
+                    let mut dst_row = output.row_mut(dst_ch);
+                    dst_row.scaled_add(c, &src_row);
+                }
+            }
+            Ok(output)
+        }
+        ChannelOp::DupMono => {
+            if src_channels != 1 {
+                return Err(anyhow!(
+                    "DupMono expects a single source channel, got {}",
+                    src_channels
+                ));
+            }
+            let mut output = Array2::<f32>::zeros((dst_channels, samples));
+            for dst_ch in 0..dst_channels {
+                output.row_mut(dst_ch).assign(&input.row(0));
+            }
+            Ok(output)
+        }
+    }
+}
+
+/// 5.1 surround channel order assumed by [`surround51_to_stereo`]:
+/// front left, front right, center, LFE, surround left, surround right.
+const SURROUND51_CHANNELS: usize = 6;
+
+/// Standard 5.1-to-stereo downmix: front L/R pass through at unity gain,
+/// center and each surround channel are scaled by `1/sqrt(2)` and summed
+/// into the matching side.
+fn surround51_to_stereo() -> ChannelOp {
+    let c = std::f32::consts::FRAC_1_SQRT_2;
+    // Rows are dst (L, R), columns are src (L, R, C, LFE, Ls, Rs).
+    ChannelOp::Remix(vec![
+        1.0, 0.0, c, 0.0, c, 0.0, //
+        0.0, 1.0, c, 0.0, 0.0, c,
+    ])
+}
+
+/// Pick the standard conversion operation for a given source/destination
+/// channel count pair, falling back to a generic broadcast/truncation for
+/// anything not covered by a named layout.
+fn plan_conversion(src_channels: usize, dst_channels: usize) -> ChannelOp {
+    if src_channels == dst_channels {
+        return ChannelOp::Passthrough;
+    }
+    match (src_channels, dst_channels) {
+        (1, _) => ChannelOp::DupMono,
+        (SURROUND51_CHANNELS, 2) => surround51_to_stereo(),
+        _ => {
+            // No standard coefficients for this pair: reorder/duplicate the
+            // leading channels as a best effort.
+            let indices = (0..dst_channels).map(|i| i % src_channels).collect();
+            ChannelOp::Reorder(indices)
+        }
+    }
+}
+
+/// Convert `input` (`src_channels` x samples) to `dst_channels`, using a
+/// standard downmix/upmix recipe when one is known for the pair, or a
+/// best-effort reorder otherwise.
+pub fn convert_channels(
+    input: ArrayView2<f32>,
+    src_channels: usize,
+    dst_channels: usize,
+) -> Result<Array2<f32>> {
+    if input.shape()[0] != src_channels {
+        return Err(anyhow!(
+            "input has {} channels, expected {}",
+            input.shape()[0],
+            src_channels
+        ));
+    }
+    let op = plan_conversion(src_channels, dst_channels);
+    apply_channel_op(input, &op, dst_channels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ndarray::array;
+
+    #[test]
+    fn mono_to_stereo_duplicates_channel() {
+        let mono = array![[1.0f32, 2.0, 3.0]];
+        let stereo = convert_channels(mono.view(), 1, 2).unwrap();
+        assert_eq!(stereo.shape(), &[2, 3]);
+        assert_eq!(stereo.row(0).to_vec(), stereo.row(1).to_vec());
+        assert_eq!(stereo.row(0).to_vec(), vec![1.0, 2.0, 3.0]);
+    }
+
+    #[test]
+    fn surround_downmix_scales_center_and_surrounds() {
+        let input = Array2::<f32>::from_shape_fn((6, 1), |(ch, _)| if ch == 2 { 1.0 } else { 0.0 });
+        let stereo = convert_channels(input.view(), 6, 2).unwrap();
+        let c = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((stereo[[0, 0]] - c).abs() < 1e-6);
+        assert!((stereo[[1, 0]] - c).abs() < 1e-6);
+    }
+
+    #[test]
+    fn passthrough_requires_matching_channels() {
+        let input = Array2::<f32>::zeros((2, 4));
+        assert!(apply_channel_op(input.view(), &ChannelOp::Passthrough, 3).is_err());
+    }
+}
@@ -12,6 +12,18 @@ use std::path::Path;
 use hound::{WavSpec, WavWriter, SampleFormat};
 use std::fs::File;
 use std::io::BufWriter;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::channels::convert_channels;
+use crate::resample::resample_sinc;
+use flacenc::bitsink::BitSink;
+use flacenc::component::BitRepr;
+use rand::Rng;
 
 /// Demucs model sample rate
 pub const DEMUCS_SAMPLE_RATE: u32 = 44100;
@@ -28,6 +40,13 @@ const CHUNK_SIZE: usize = 441000;
 /// Overlap between chunks (in samples) - about 1 second
 const OVERLAP: usize = 44100;
 
+/// Default exponent applied to the raised transition window, matching
+/// Demucs' own `transition_power` test-time default.
+const DEFAULT_TRANSITION_POWER: f32 = 1.0;
+
+/// Default maximum random shift for the shift trick - about 0.5s at 44.1kHz.
+const DEFAULT_MAX_SHIFT_SAMPLES: usize = DEMUCS_SAMPLE_RATE as usize / 2;
+
 /// Separation result containing paths to output stem files
 #[derive(Debug, Clone)]
 pub struct SeparationResult {
@@ -40,60 +59,170 @@ pub struct SeparationResult {
 pub struct DemucsModel {
     session: Session,
     sample_rate: u32,
+    /// Stem names in the order the model outputs them; defaults to
+    /// [`STEM_NAMES`] when the model isn't loaded from the registry.
+    stem_names: Vec<String>,
+    /// Channels per stem the model produces.
+    output_channels: usize,
+    /// Exponent on the raised transition window used for chunk overlap-add.
+    transition_power: f32,
+    /// Number of randomly-shifted inference passes to average (the "shift
+    /// trick"). `1` disables shifting and runs inference once.
+    num_shifts: usize,
+    /// Maximum random shift offset, in samples, used by the shift trick.
+    max_shift_samples: usize,
 }
 
 impl DemucsModel {
-    /// Load a Demucs ONNX model from file
+    /// Load a Demucs ONNX model from file, assuming the default
+    /// `htdemucs_6s` stem layout.
     pub fn load(model_path: &Path) -> Result<Self> {
+        Self::load_with_info(model_path, None)
+    }
+
+    /// Load a model, deriving its stem names and output channel count from
+    /// a [`crate::models::ModelInfo`] entry instead of the hardcoded
+    /// defaults.
+    pub fn load_from_registry(model_path: &Path, info: &crate::models::ModelInfo) -> Result<Self> {
+        Self::load_with_info(model_path, Some(info))
+    }
+
+    fn load_with_info(model_path: &Path, info: Option<&crate::models::ModelInfo>) -> Result<Self> {
         // Initialize ONNX Runtime
         let session = Session::builder()?
             .with_optimization_level(GraphOptimizationLevel::Level3)?
             .commit_from_file(model_path)
             .context("Failed to load ONNX model")?;
-        
+
+        let (stem_names, output_channels) = match info {
+            Some(info) => (
+                info.stem_names.iter().map(|s| s.to_string()).collect(),
+                info.output_channels,
+            ),
+            None => (STEM_NAMES.iter().map(|s| s.to_string()).collect(), 2),
+        };
+
         Ok(Self {
             session,
             sample_rate: DEMUCS_SAMPLE_RATE,
+            stem_names,
+            output_channels,
+            transition_power: DEFAULT_TRANSITION_POWER,
+            num_shifts: 1,
+            max_shift_samples: DEFAULT_MAX_SHIFT_SAMPLES,
         })
     }
-    
+
     /// Get the expected sample rate for input audio
     pub fn sample_rate(&self) -> u32 {
         self.sample_rate
     }
-    
+
+    /// Stem names in model output order.
+    pub fn stem_names(&self) -> &[String] {
+        &self.stem_names
+    }
+
+    /// Channels per stem this model produces.
+    pub fn output_channels(&self) -> usize {
+        self.output_channels
+    }
+
+    /// Set the exponent on the raised transition window used to blend
+    /// overlapping chunks. Higher values narrow the transition toward each
+    /// chunk's center.
+    pub fn with_transition_power(mut self, transition_power: f32) -> Self {
+        self.transition_power = transition_power;
+        self
+    }
+
+    /// Enable the shift trick: run inference `num_shifts` times with a
+    /// random offset up to `max_shift_samples`, each time shifting the
+    /// output back before averaging. `num_shifts <= 1` disables shifting.
+    pub fn with_shifts(mut self, num_shifts: usize, max_shift_samples: usize) -> Self {
+        self.num_shifts = num_shifts.max(1);
+        self.max_shift_samples = max_shift_samples;
+        self
+    }
+
     /// Separate audio into stems
-    /// 
+    ///
     /// Input: stereo audio samples as [channels, samples] (2 x N)
     /// Output: separated stems as [stems, channels, samples] (6 x 2 x N)
     pub fn separate(&mut self, audio: ArrayView2<f32>) -> Result<Array3<f32>> {
+        if self.num_shifts <= 1 {
+            return self.separate_once(audio);
+        }
+
+        let total_samples = audio.shape()[1];
+        let pad = self.max_shift_samples;
+        // Pad both ends by the maximum shift before shifting so shift_right
+        // only ever drops/zero-fills padding, never real audio; the padding
+        // is cropped back off below once the shifted passes are averaged.
+        let padded = pad_audio(audio, pad);
+
+        let mut rng = rand::thread_rng();
+        let mut acc: Option<Array3<f32>> = None;
+
+        for _ in 0..self.num_shifts {
+            let shift = if self.max_shift_samples == 0 {
+                0
+            } else {
+                rng.gen_range(0..=self.max_shift_samples)
+            };
+
+            let shifted_input = shift_right(padded.view(), shift);
+            let shifted_output = self.separate_once(shifted_input.view())?;
+            let output = shift_left(&shifted_output, shift);
+
+            acc = Some(match acc {
+                Some(mut sum) => {
+                    sum += &output;
+                    sum
+                }
+                None => output,
+            });
+        }
+
+        let mut result = acc.expect("num_shifts > 1 guarantees at least one pass");
+        result.mapv_inplace(|x| x / self.num_shifts as f32);
+        Ok(result.slice(s![.., .., pad..pad + total_samples]).to_owned())
+    }
+
+    /// Run one (unshifted) separation pass, chunking and overlap-adding as
+    /// needed.
+    fn separate_once(&mut self, audio: ArrayView2<f32>) -> Result<Array3<f32>> {
         let (channels, total_samples) = (audio.shape()[0], audio.shape()[1]);
-        
-        if channels != 2 {
-            return Err(anyhow!("Expected stereo audio (2 channels), got {}", channels));
+
+        if channels != self.output_channels {
+            return Err(anyhow!(
+                "Expected {}-channel audio, got {}",
+                self.output_channels,
+                channels
+            ));
         }
-        
+
         if total_samples == 0 {
             return Err(anyhow!("Empty audio input"));
         }
-        
+
         // For short audio, process in one go
         if total_samples <= CHUNK_SIZE {
             return self.process_chunk(audio);
         }
-        
+
         // For longer audio, process in overlapping chunks
-        let num_stems = STEM_NAMES.len();
+        let num_stems = self.stem_names.len();
         let mut output = Array3::<f32>::zeros((num_stems, channels, total_samples));
         let mut weights = Array2::<f32>::zeros((channels, total_samples));
-        
+
         let step = CHUNK_SIZE - OVERLAP;
         let mut start = 0;
-        
+
         while start < total_samples {
             let end = (start + CHUNK_SIZE).min(total_samples);
             let chunk = audio.slice(s![.., start..end]);
-            
+
             // Pad if needed
             let chunk = if chunk.shape()[1] < CHUNK_SIZE {
                 let mut padded = Array2::<f32>::zeros((2, CHUNK_SIZE));
@@ -102,36 +231,32 @@ impl DemucsModel {
             } else {
                 chunk.to_owned()
             };
-            
+
             // Process chunk
             let chunk_output = self.process_chunk(chunk.view())?;
-            
-            // Calculate window weights for overlap-add
+
+            // Demucs' raised transition window: w[i] = (min(i+1, L-i))^power,
+            // weighted highest at the chunk's center so overlap-add stays
+            // energy-smooth across the whole chunk, not just its edges.
             let chunk_len = end - start;
             for i in 0..chunk_len {
-                // Triangular window for smooth blending
-                let weight = if i < OVERLAP && start > 0 {
-                    i as f32 / OVERLAP as f32
-                } else if i >= chunk_len - OVERLAP && end < total_samples {
-                    (chunk_len - i) as f32 / OVERLAP as f32
-                } else {
-                    1.0
-                };
-                
+                let raw = (i + 1).min(chunk_len - i) as f32;
+                let weight = raw.powf(self.transition_power);
+
                 for stem_idx in 0..num_stems {
                     for ch in 0..channels {
                         output[[stem_idx, ch, start + i]] += chunk_output[[stem_idx, ch, i]] * weight;
                     }
                 }
-                
+
                 for ch in 0..channels {
                     weights[[ch, start + i]] += weight;
                 }
             }
-            
+
             start += step;
         }
-        
+
         // Normalize by weights
         for stem_idx in 0..num_stems {
             for ch in 0..channels {
@@ -142,10 +267,10 @@ impl DemucsModel {
                 }
             }
         }
-        
+
         Ok(output)
     }
-    
+
     /// Process a single chunk through the model
     fn process_chunk(&mut self, audio: ArrayView2<f32>) -> Result<Array3<f32>> {
         let (channels, samples) = (audio.shape()[0], audio.shape()[1]);
@@ -193,6 +318,53 @@ impl DemucsModel {
     }
 }
 
+/// Zero-pad `audio` by `pad` samples on both ends. Used by the shift trick
+/// to give [`shift_right`]/[`shift_left`] room to move the signal within the
+/// buffer without dropping real content at the edges, since `shift` is
+/// bounded by `pad`.
+fn pad_audio(audio: ArrayView2<f32>, pad: usize) -> Array2<f32> {
+    let (channels, samples) = (audio.shape()[0], audio.shape()[1]);
+    if pad == 0 {
+        return audio.to_owned();
+    }
+    let mut padded = Array2::<f32>::zeros((channels, samples + 2 * pad));
+    padded.slice_mut(s![.., pad..pad + samples]).assign(&audio);
+    padded
+}
+
+/// Pad-shift audio right by `shift` samples: zero-fill the first `shift`
+/// samples and drop the last `shift` samples, keeping the buffer length
+/// unchanged. Used by the shift trick to vary chunk edge alignment; callers
+/// must ensure `shift` doesn't exceed the zero padding from [`pad_audio`] so
+/// only padding (not real content) is shifted off the edges.
+fn shift_right(audio: ArrayView2<f32>, shift: usize) -> Array2<f32> {
+    let (channels, samples) = (audio.shape()[0], audio.shape()[1]);
+    if shift == 0 || shift >= samples {
+        return audio.to_owned();
+    }
+    let mut shifted = Array2::<f32>::zeros((channels, samples));
+    shifted
+        .slice_mut(s![.., shift..])
+        .assign(&audio.slice(s![.., ..samples - shift]));
+    shifted
+}
+
+/// Undo [`shift_right`] on separation output: drop the first `shift`
+/// samples and zero-fill the tail, restoring the original alignment. As
+/// with [`shift_right`], the tail zero-fill only discards padding (not real
+/// content) as long as the input was padded by at least `shift` samples.
+fn shift_left(output: &Array3<f32>, shift: usize) -> Array3<f32> {
+    let (stems, channels, samples) = output.dim();
+    if shift == 0 || shift >= samples {
+        return output.clone();
+    }
+    let mut shifted = Array3::<f32>::zeros((stems, channels, samples));
+    shifted
+        .slice_mut(s![.., .., ..samples - shift])
+        .assign(&output.slice(s![.., .., shift..]));
+    shifted
+}
+
 /// Resample audio to target sample rate
 pub fn resample_audio(audio: ArrayView2<f32>, from_rate: u32, to_rate: u32) -> Result<Array2<f32>> {
     if from_rate == to_rate {
@@ -265,6 +437,151 @@ pub fn interleaved_to_array(audio: &[f32], channels: usize) -> Array2<f32> {
     result
 }
 
+/// Output container/codec for exported stems.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum StemFormat {
+    /// Uncompressed 32-bit float WAV (previous behavior).
+    #[default]
+    WavF32,
+    /// 16-bit integer WAV, TPDF-dithered down from the f32 separation output.
+    Wav16,
+    /// Lossless FLAC at the given bit depth (16 or 24).
+    Flac { bits: u16 },
+    /// Lossy OGG Vorbis at a quality factor in `[-0.1, 1.0]` (libvorbis
+    /// convention: higher is better quality/larger file).
+    OggVorbis { quality: f32 },
+}
+
+impl StemFormat {
+    /// File extension (without the dot) this format should be saved with.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            StemFormat::WavF32 | StemFormat::Wav16 => "wav",
+            StemFormat::Flac { .. } => "flac",
+            StemFormat::OggVorbis { .. } => "ogg",
+        }
+    }
+}
+
+/// Quantize `samples` from f32 to signed PCM of `bits` width, adding
+/// triangular (TPDF) dither at +-1 LSB before truncation to avoid harsh
+/// requantization artifacts on quiet stems.
+fn dither_quantize(samples: &[f32], bits: u16) -> Vec<i32> {
+    let max_val = (1i64 << (bits - 1)) - 1;
+    let mut rng = rand::thread_rng();
+
+    samples
+        .iter()
+        .map(|&s| {
+            // Sum of two uniform(-0.5, 0.5) sources gives a triangular
+            // distribution spanning +-1 LSB.
+            let dither: f32 = rng.gen::<f32>() - 0.5 + (rng.gen::<f32>() - 0.5);
+            let scaled = (s as f64) * max_val as f64 + dither as f64;
+            scaled.round().clamp(-(max_val as f64) - 1.0, max_val as f64) as i32
+        })
+        .collect()
+}
+
+/// Save a stem in the requested [`StemFormat`].
+pub fn save_stem(path: &Path, audio: ArrayView2<f32>, sample_rate: u32, format: StemFormat) -> Result<()> {
+    match format {
+        StemFormat::WavF32 => save_wav(path, audio, sample_rate),
+        StemFormat::Wav16 => save_wav_pcm16(path, audio, sample_rate),
+        StemFormat::Flac { bits } => save_flac(path, audio, sample_rate, bits),
+        StemFormat::OggVorbis { quality } => save_ogg_vorbis(path, audio, sample_rate, quality),
+    }
+}
+
+/// Save audio as dithered 16-bit PCM WAV.
+fn save_wav_pcm16(path: &Path, audio: ArrayView2<f32>, sample_rate: u32) -> Result<()> {
+    let channels = audio.shape()[0] as u16;
+    let samples = audio.shape()[1];
+
+    let spec = WavSpec {
+        channels,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: SampleFormat::Int,
+    };
+
+    let quantized_channels: Vec<Vec<i32>> = (0..channels as usize)
+        .map(|ch| dither_quantize(&audio.row(ch).to_vec(), 16))
+        .collect();
+
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    let mut wav_writer = WavWriter::new(writer, spec)?;
+
+    for i in 0..samples {
+        for ch in 0..channels as usize {
+            wav_writer.write_sample(quantized_channels[ch][i] as i16)?;
+        }
+    }
+    wav_writer.finalize()?;
+    Ok(())
+}
+
+/// Save audio as lossless FLAC at the given bit depth (16 or 24).
+fn save_flac(path: &Path, audio: ArrayView2<f32>, sample_rate: u32, bits: u16) -> Result<()> {
+    let channels = audio.shape()[0];
+    let samples = audio.shape()[1];
+
+    // flacenc's `MemSource` wants interleaved PCM.
+    let quantized_channels: Vec<Vec<i32>> = (0..channels)
+        .map(|ch| dither_quantize(&audio.row(ch).to_vec(), bits))
+        .collect();
+    let mut interleaved = Vec::with_capacity(samples * channels);
+    for i in 0..samples {
+        for ch in 0..channels {
+            interleaved.push(quantized_channels[ch][i]);
+        }
+    }
+
+    let config = flacenc::config::Encoder::default();
+    let source = flacenc::source::MemSource::from_samples(
+        &interleaved,
+        channels,
+        bits as usize,
+        sample_rate as usize,
+    );
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| anyhow!("FLAC encoding failed: {:?}", e))?;
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|e| anyhow!("FLAC bitstream write failed: {:?}", e))?;
+    std::fs::write(path, sink.as_slice())?;
+    Ok(())
+}
+
+/// Save audio as lossy OGG Vorbis at the given quality factor.
+fn save_ogg_vorbis(path: &Path, audio: ArrayView2<f32>, sample_rate: u32, quality: f32) -> Result<()> {
+    let channels = audio.shape()[0];
+
+    let channel_data: Vec<Vec<f32>> = (0..channels).map(|ch| audio.row(ch).to_vec()).collect();
+
+    let sample_rate = std::num::NonZeroU32::new(sample_rate)
+        .ok_or_else(|| anyhow!("invalid sample rate"))?;
+    let channel_count = std::num::NonZeroU8::new(channels as u8)
+        .ok_or_else(|| anyhow!("invalid channel count"))?;
+
+    let file = File::create(path)?;
+    let mut encoder = vorbis_rs::VorbisEncoderBuilder::new(sample_rate, channel_count, file)
+        .context("Failed to configure Vorbis encoder")?
+        .bitrate_management_strategy(vorbis_rs::VorbisBitrateManagementStrategy::QualityVbr {
+            target_quality: quality,
+        })
+        .build()
+        .context("Failed to build Vorbis encoder")?;
+
+    encoder
+        .encode_audio_block(&channel_data)
+        .context("Failed to encode Vorbis audio block")?;
+    encoder.finish().context("Failed to finalize Vorbis stream")?;
+    Ok(())
+}
+
 /// Save audio array to WAV file
 pub fn save_wav(path: &Path, audio: ArrayView2<f32>, sample_rate: u32) -> Result<()> {
     let channels = audio.shape()[0] as u16;
@@ -292,26 +609,112 @@ pub fn save_wav(path: &Path, audio: ArrayView2<f32>, sample_rate: u32) -> Result
     Ok(())
 }
 
+/// Decode an audio file to its original channel layout (no mono mixdown),
+/// returning the samples as (channels, samples) alongside the source sample
+/// rate.
+fn load_audio_multichannel(path: &Path) -> Result<(Array2<f32>, u32)> {
+    let data = std::fs::read(path).context("Failed to read input file")?;
+    let cursor = std::io::Cursor::new(data);
+    let mss = MediaSourceStream::new(Box::new(cursor), Default::default());
+
+    let hint = Hint::new();
+    let format_opts = FormatOptions::default();
+    let metadata_opts = MetadataOptions::default();
+
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &format_opts, &metadata_opts)
+        .context("Failed to probe audio format")?;
+
+    let mut format = probed.format;
+
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != symphonia::core::codecs::CODEC_TYPE_NULL)
+        .ok_or_else(|| anyhow!("No supported audio track found"))?;
+
+    let sample_rate = track
+        .codec_params
+        .sample_rate
+        .ok_or_else(|| anyhow!("Unknown sample rate"))?;
+    let channels = track
+        .codec_params
+        .channels
+        .map(|c| c.count() as u16)
+        .unwrap_or(2);
+
+    let decoder_opts = DecoderOptions::default();
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &decoder_opts)
+        .context("Failed to create audio decoder")?;
+
+    let track_id = track.id;
+    let mut samples: Vec<f32> = Vec::new();
+
+    loop {
+        let packet = match format.next_packet() {
+            Ok(p) => p,
+            Err(symphonia::core::errors::Error::IoError(e))
+                if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+            {
+                break
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(d) => d,
+            Err(symphonia::core::errors::Error::DecodeError(_)) => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        let spec = *decoded.spec();
+        let duration = decoded.capacity() as u64;
+
+        let mut sample_buf = SampleBuffer::<f32>::new(duration, spec);
+        sample_buf.copy_interleaved_ref(decoded);
+
+        samples.extend_from_slice(sample_buf.samples());
+    }
+
+    let audio = interleaved_to_array(&samples, channels as usize);
+    Ok((audio, sample_rate))
+}
+
+/// Output sample rate handling for [`separate_file`].
+#[derive(Debug, Clone, Copy, Default)]
+pub enum OutputSampleRate {
+    /// Always write stems at [`DEMUCS_SAMPLE_RATE`] (previous behavior).
+    #[default]
+    Demucs,
+    /// Resample stems back to the input file's original sample rate before
+    /// writing, via [`resample_sinc`].
+    MatchInput,
+}
+
 /// High-level function to separate an audio file into stems
 pub fn separate_file(
     model: &mut DemucsModel,
     input_path: &Path,
     output_dir: &Path,
+    output_rate: OutputSampleRate,
+    output_format: StemFormat,
 ) -> Result<SeparationResult> {
-    use crate::load_audio_from_bytes;
-    
-    // Read input file
-    let input_data = std::fs::read(input_path)
-        .context("Failed to read input file")?;
-    
-    // Decode audio
-    let (samples, sample_rate) = load_audio_from_bytes(&input_data)
+    // Decode preserving the original channel layout, so a true stereo (or
+    // surround) field survives into the model instead of being collapsed to
+    // mono first.
+    let (audio, sample_rate) = load_audio_multichannel(input_path)
         .context("Failed to decode audio")?;
-    
-    // Convert to stereo if needed (our load function returns mono)
-    // We need to reload as stereo for proper separation
-    let audio = mono_to_stereo(&samples.iter().map(|&x| x as f32).collect::<Vec<_>>());
-    
+    let src_channels = audio.shape()[0];
+
+    // Map the source layout to the 2 channels Demucs expects.
+    let audio = convert_channels(audio.view(), src_channels, 2)
+        .context("Failed to convert input to model channel layout")?;
+
     // Resample if needed
     let audio = if sample_rate != DEMUCS_SAMPLE_RATE {
         eprintln!("Resampling from {} to {} Hz...", sample_rate, DEMUCS_SAMPLE_RATE);
@@ -319,11 +722,11 @@ pub fn separate_file(
     } else {
         audio
     };
-    
+
     // Run separation
     eprintln!("Running Demucs separation...");
     let stems = model.separate(audio.view())?;
-    
+
     // Create output directory
     let stem_dir = output_dir.join("htdemucs_6s").join(
         input_path.file_stem()
@@ -331,23 +734,39 @@ pub fn separate_file(
             .unwrap_or_else(|| "output".to_string())
     );
     std::fs::create_dir_all(&stem_dir)?;
-    
+
     // Save stems
     let mut result_stems = Vec::new();
-    
-    for (i, stem_name) in STEM_NAMES.iter().enumerate() {
+    let stem_names = model.stem_names().to_vec();
+
+    for (i, stem_name) in stem_names.iter().enumerate() {
         let stem_audio = stems.slice(s![i, .., ..]);
-        let stem_path = stem_dir.join(format!("{}.wav", stem_name));
-        
+        // Map each stem back to the caller's original channel layout.
+        let stem_audio = convert_channels(stem_audio, model.output_channels(), src_channels)
+            .context("Failed to convert stem to output channel layout")?;
+
+        let (stem_audio, out_rate) = match output_rate {
+            OutputSampleRate::Demucs => (stem_audio, DEMUCS_SAMPLE_RATE),
+            OutputSampleRate::MatchInput if sample_rate == DEMUCS_SAMPLE_RATE => {
+                (stem_audio, DEMUCS_SAMPLE_RATE)
+            }
+            OutputSampleRate::MatchInput => {
+                eprintln!("Resampling {} back to {} Hz...", stem_name, sample_rate);
+                (resample_sinc(stem_audio.view(), DEMUCS_SAMPLE_RATE, sample_rate), sample_rate)
+            }
+        };
+
+        let stem_path = stem_dir.join(format!("{}.{}", stem_name, output_format.extension()));
+
         eprintln!("Saving {}...", stem_name);
-        save_wav(&stem_path, stem_audio, DEMUCS_SAMPLE_RATE)?;
-        
+        save_stem(&stem_path, stem_audio.view(), out_rate, output_format)?;
+
         result_stems.push((
             stem_name.to_string(),
             stem_path.to_string_lossy().to_string(),
         ));
     }
-    
+
     Ok(SeparationResult {
         model: "htdemucs_6s".to_string(),
         input_path: input_path.to_string_lossy().to_string(),
@@ -0,0 +1,288 @@
+//! Per-component audio descriptors (tempo, spectral, timbre) used to help
+//! label which principal component carries percussion vs. harmonic content.
+
+use crate::stft;
+use ndarray::Array2;
+use serde::Serialize;
+
+/// Descriptor vector for a single reconstructed component.
+#[derive(Debug, Clone, Serialize)]
+pub struct ComponentFeatures {
+    /// Root-mean-square energy of the time-domain signal.
+    pub rms: f64,
+    /// Fraction of adjacent sample pairs that change sign.
+    pub zero_crossing_rate: f64,
+    /// Magnitude-weighted mean frequency (Hz), averaged across frames.
+    pub spectral_centroid: f64,
+    /// Frequency (Hz) below which 85% of the spectral energy is
+    /// concentrated, averaged across frames.
+    pub spectral_rolloff: f64,
+    /// Geometric mean over arithmetic mean of the magnitude bins, averaged
+    /// across frames; near `0` for tonal/harmonic content, near `1` for
+    /// noise-like/percussive content.
+    pub spectral_flatness: f64,
+    /// Estimated tempo in beats per minute, or `0.0` if no clear periodicity
+    /// was found in the onset-strength envelope.
+    pub estimated_tempo_bpm: f64,
+}
+
+/// Fraction of total spectral energy that [`spectral_rolloff`] looks for.
+const ROLLOFF_THRESHOLD: f64 = 0.85;
+
+/// Span of the moving average subtracted from the onset envelope before
+/// autocorrelation: `ONSET_SMOOTH_WINDOW / 2` frames on each side of the
+/// current frame, plus the frame itself.
+const ONSET_SMOOTH_WINDOW: usize = 16;
+
+/// Tempo search range, in beats per minute.
+const MIN_TEMPO_BPM: f64 = 60.0;
+const MAX_TEMPO_BPM: f64 = 200.0;
+
+/// Compute the descriptor vector for one reconstructed component.
+pub fn compute_features(samples: &[f64], sample_rate: u32, window_size: usize, hop_size: usize) -> ComponentFeatures {
+    let spectrogram = stft(samples, window_size, hop_size);
+    let magnitude = spectrogram.mapv(|c| c.norm());
+
+    ComponentFeatures {
+        rms: rms_energy(samples),
+        zero_crossing_rate: zero_crossing_rate(samples),
+        spectral_centroid: spectral_centroid(&magnitude, sample_rate, window_size),
+        spectral_rolloff: spectral_rolloff(&magnitude, sample_rate, window_size),
+        spectral_flatness: spectral_flatness(&magnitude),
+        estimated_tempo_bpm: estimate_tempo(&magnitude, sample_rate, hop_size),
+    }
+}
+
+fn rms_energy(samples: &[f64]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|&s| s * s).sum::<f64>() / samples.len() as f64).sqrt()
+}
+
+fn zero_crossing_rate(samples: &[f64]) -> f64 {
+    if samples.len() < 2 {
+        return 0.0;
+    }
+    let crossings = samples
+        .windows(2)
+        .filter(|w| (w[0] >= 0.0) != (w[1] >= 0.0))
+        .count();
+    crossings as f64 / (samples.len() - 1) as f64
+}
+
+fn bin_frequency(bin: usize, sample_rate: u32, window_size: usize) -> f64 {
+    bin as f64 * sample_rate as f64 / window_size as f64
+}
+
+fn spectral_centroid(magnitude: &Array2<f64>, sample_rate: u32, window_size: usize) -> f64 {
+    let (num_bins, num_frames) = magnitude.dim();
+    if num_frames == 0 {
+        return 0.0;
+    }
+
+    let mut total = 0.0;
+    let mut frames_counted = 0;
+    for frame in magnitude.columns() {
+        let energy: f64 = frame.sum();
+        if energy <= 1e-12 {
+            continue;
+        }
+        let weighted: f64 = (0..num_bins)
+            .map(|k| bin_frequency(k, sample_rate, window_size) * frame[k])
+            .sum();
+        total += weighted / energy;
+        frames_counted += 1;
+    }
+
+    if frames_counted == 0 {
+        0.0
+    } else {
+        total / frames_counted as f64
+    }
+}
+
+fn spectral_rolloff(magnitude: &Array2<f64>, sample_rate: u32, window_size: usize) -> f64 {
+    let (num_bins, num_frames) = magnitude.dim();
+    if num_frames == 0 {
+        return 0.0;
+    }
+
+    let mut total = 0.0;
+    let mut frames_counted = 0;
+    for frame in magnitude.columns() {
+        let total_energy: f64 = frame.sum();
+        if total_energy <= 1e-12 {
+            continue;
+        }
+        let target = total_energy * ROLLOFF_THRESHOLD;
+        let mut cumulative = 0.0;
+        let mut rolloff_bin = num_bins - 1;
+        for k in 0..num_bins {
+            cumulative += frame[k];
+            if cumulative >= target {
+                rolloff_bin = k;
+                break;
+            }
+        }
+        total += bin_frequency(rolloff_bin, sample_rate, window_size);
+        frames_counted += 1;
+    }
+
+    if frames_counted == 0 {
+        0.0
+    } else {
+        total / frames_counted as f64
+    }
+}
+
+/// Ratio of the geometric mean to the arithmetic mean of the magnitude
+/// bins in each frame, averaged across frames. Near `0` for tonal/harmonic
+/// spectra dominated by a few peaks, near `1` for flat, noise-like spectra.
+fn spectral_flatness(magnitude: &Array2<f64>) -> f64 {
+    let (num_bins, num_frames) = magnitude.dim();
+    if num_frames == 0 || num_bins == 0 {
+        return 0.0;
+    }
+
+    const EPS: f64 = 1e-12;
+    let mut total = 0.0;
+    let mut frames_counted = 0;
+    for frame in magnitude.columns() {
+        let arithmetic_mean: f64 = frame.sum() / num_bins as f64;
+        if arithmetic_mean <= EPS {
+            continue;
+        }
+        let log_sum: f64 = frame.iter().map(|&m| (m.max(EPS)).ln()).sum();
+        let geometric_mean = (log_sum / num_bins as f64).exp();
+        total += geometric_mean / arithmetic_mean;
+        frames_counted += 1;
+    }
+
+    if frames_counted == 0 {
+        0.0
+    } else {
+        total / frames_counted as f64
+    }
+}
+
+/// Estimate tempo from the magnitude spectrogram's onset-strength envelope:
+/// positive spectral flux between consecutive frames, smoothed by
+/// subtracting a local moving average, then autocorrelated to find the
+/// strongest periodicity within the `MIN_TEMPO_BPM..=MAX_TEMPO_BPM` range.
+fn estimate_tempo(magnitude: &Array2<f64>, sample_rate: u32, hop_size: usize) -> f64 {
+    let (num_bins, num_frames) = magnitude.dim();
+    if num_frames < 3 {
+        return 0.0;
+    }
+
+    let mut onset = vec![0.0; num_frames];
+    for t in 1..num_frames {
+        onset[t] = (0..num_bins)
+            .map(|k| (magnitude[[k, t]] - magnitude[[k, t - 1]]).max(0.0))
+            .sum();
+    }
+
+    let envelope: Vec<f64> = onset
+        .iter()
+        .enumerate()
+        .map(|(i, &v)| {
+            let lo = i.saturating_sub(ONSET_SMOOTH_WINDOW / 2);
+            let hi = (i + ONSET_SMOOTH_WINDOW / 2 + 1).min(onset.len());
+            let local_avg = onset[lo..hi].iter().sum::<f64>() / (hi - lo) as f64;
+            (v - local_avg).max(0.0)
+        })
+        .collect();
+
+    let lag_for_bpm = |bpm: f64| (60.0 * sample_rate as f64) / (hop_size as f64 * bpm);
+    let lag_min = lag_for_bpm(MAX_TEMPO_BPM).ceil().max(1.0) as usize;
+    let lag_max = (lag_for_bpm(MIN_TEMPO_BPM).floor() as usize).min(envelope.len().saturating_sub(1));
+
+    if lag_min >= lag_max {
+        return 0.0;
+    }
+
+    let mut best_lag = lag_min;
+    let mut best_corr = f64::MIN;
+    for lag in lag_min..=lag_max {
+        let corr: f64 = (0..envelope.len() - lag)
+            .map(|t| envelope[t] * envelope[t + lag])
+            .sum();
+        if corr > best_corr {
+            best_corr = corr;
+            best_lag = lag;
+        }
+    }
+
+    if best_corr <= 0.0 {
+        return 0.0;
+    }
+
+    60.0 * sample_rate as f64 / (hop_size as f64 * best_lag as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rms_of_constant_signal_matches_amplitude() {
+        let samples = vec![0.5; 1000];
+        assert!((rms_energy(&samples) - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn zero_crossing_rate_of_alternating_signal_is_near_one() {
+        let samples: Vec<f64> = (0..1000).map(|i| if i % 2 == 0 { 1.0 } else { -1.0 }).collect();
+        assert!(zero_crossing_rate(&samples) > 0.99);
+    }
+
+    #[test]
+    fn zero_crossing_rate_of_dc_signal_is_zero() {
+        let samples = vec![1.0; 1000];
+        assert_eq!(zero_crossing_rate(&samples), 0.0);
+    }
+
+    #[test]
+    fn spectral_flatness_of_pure_tone_is_lower_than_white_noise() {
+        let sample_rate = 22050u32;
+        let window_size = 1024;
+        let hop_size = 512;
+
+        let tone: Vec<f64> = (0..window_size * 4)
+            .map(|i| (2.0 * std::f64::consts::PI * 440.0 * i as f64 / sample_rate as f64).sin())
+            .collect();
+        let mut seed = 12345u64;
+        let noise: Vec<f64> = (0..window_size * 4)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+                ((seed >> 33) as f64 / u32::MAX as f64) * 2.0 - 1.0
+            })
+            .collect();
+
+        let tone_features = compute_features(&tone, sample_rate, window_size, hop_size);
+        let noise_features = compute_features(&noise, sample_rate, window_size, hop_size);
+
+        assert!(tone_features.spectral_flatness < noise_features.spectral_flatness);
+    }
+
+    #[test]
+    fn compute_features_on_click_track_estimates_plausible_tempo() {
+        let sample_rate = 22050u32;
+        let window_size = 1024;
+        let hop_size = 256;
+        let bpm = 120.0;
+        let period_samples = (60.0 * sample_rate as f64 / bpm) as usize;
+
+        let mut samples = vec![0.0; period_samples * 8];
+        for click_start in (0..samples.len()).step_by(period_samples) {
+            for i in 0..20.min(samples.len() - click_start) {
+                samples[click_start + i] = 1.0 - i as f64 / 20.0;
+            }
+        }
+
+        let features = compute_features(&samples, sample_rate, window_size, hop_size);
+        assert!(features.estimated_tempo_bpm >= 60.0 && features.estimated_tempo_bpm <= 200.0);
+        assert!(features.rms > 0.0);
+    }
+}
@@ -2,8 +2,12 @@
 //! 
 //! These functions provide a C-compatible interface for use from Swift/Objective-C
 
-use crate::{load_audio_from_bytes, encode_wav_to_bytes, mix_components, stft, istft, pca_decompose};
+use crate::features::compute_features;
+use crate::mdct::{imdct, mdct};
+use crate::resample::{resample, InterpolationMode};
+use crate::{load_audio_from_bytes, encode_wav_to_bytes, mix_components, stft, istft, pca_decompose, pca_decompose_real};
 use libc::{c_char, c_double, c_uint, size_t};
+use std::collections::VecDeque;
 use std::ptr;
 use std::slice;
 
@@ -13,6 +17,10 @@ pub struct PcaSession {
     pub eigenvalues: Vec<f64>,
     pub variance_ratios: Vec<f64>,
     pub sample_rate: u32,
+    /// Window/hop sizes used to produce `components`, kept so
+    /// `pca_get_component_spectrogram` can recompute a matching STFT.
+    window_size: usize,
+    hop_size: usize,
 }
 
 /// Result structure returned to Swift
@@ -40,8 +48,26 @@ pub struct ComponentInfoFFI {
     pub variance_ratio: c_double,
 }
 
+/// Time-frequency transform used by `pca_process_audio` before running PCA.
+///
+/// `Stft` is the original complex, redundant transform. `Mdct` runs PCA on
+/// half as many (real, critically-sampled) coefficients and reconstructs
+/// components without discarding phase, at the cost of requiring
+/// `hop_size == window_size / 2`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransformModeFFI {
+    Stft = 0,
+    Mdct = 1,
+}
+
 /// Process audio data and return a session handle
-/// 
+///
+/// `transform_mode == Mdct` requires `hop_size == window_size / 2`; any other
+/// hop size only cancels time-domain aliasing for `Stft` mode, so `Mdct`
+/// calls with a mismatched hop are rejected with an error result rather than
+/// silently returning aliased audio.
+///
 /// # Safety
 /// - `data` must be a valid pointer to `data_len` bytes
 /// - Caller must free the returned session with `pca_session_free`
@@ -52,6 +78,7 @@ pub unsafe extern "C" fn pca_process_audio(
     num_components: c_uint,
     window_size: c_uint,
     hop_size: c_uint,
+    transform_mode: TransformModeFFI,
 ) -> PcaResultFFI {
     if data.is_null() {
         return PcaResultFFI {
@@ -63,7 +90,7 @@ pub unsafe extern "C" fn pca_process_audio(
     }
 
     let audio_data = slice::from_raw_parts(data, data_len);
-    
+
     // Load and decode audio
     let (samples, sample_rate) = match load_audio_from_bytes(audio_data) {
         Ok(result) => result,
@@ -77,28 +104,81 @@ pub unsafe extern "C" fn pca_process_audio(
         }
     };
 
-    // Compute STFT
-    let spectrogram = stft(&samples, window_size as usize, hop_size as usize);
-    
-    // Apply PCA
-    let (component_specs, eigenvalues, variance_ratios) = match pca_decompose(&spectrogram, num_components as usize) {
-        Ok(result) => result,
-        Err(e) => {
-            return PcaResultFFI {
-                session: ptr::null_mut(),
-                num_components: 0,
-                sample_rate: 0,
-                error: string_to_c(format!("PCA failed: {}", e)),
-            };
+    let window_size = window_size as usize;
+    let hop_size = hop_size as usize;
+
+    // Reconstruct audio for each component, via whichever transform was
+    // requested.
+    match transform_mode {
+        TransformModeFFI::Stft => {
+            let spectrogram = stft(&samples, window_size, hop_size);
+            let (component_specs, eigenvalues, variance_ratios) =
+                match pca_decompose(&spectrogram, num_components as usize) {
+                    Ok(result) => result,
+                    Err(e) => {
+                        return PcaResultFFI {
+                            session: ptr::null_mut(),
+                            num_components: 0,
+                            sample_rate: 0,
+                            error: string_to_c(format!("PCA failed: {}", e)),
+                        };
+                    }
+                };
+
+            let components: Vec<Vec<f64>> = component_specs
+                .iter()
+                .map(|spec| istft(spec, window_size, hop_size, samples.len()))
+                .collect();
+
+            finish_process_audio(components, eigenvalues, variance_ratios, sample_rate, window_size, hop_size)
         }
-    };
+        TransformModeFFI::Mdct => {
+            if hop_size != window_size / 2 {
+                return PcaResultFFI {
+                    session: ptr::null_mut(),
+                    num_components: 0,
+                    sample_rate: 0,
+                    error: string_to_c(format!(
+                        "Mdct transform mode requires hop_size == window_size / 2 (got window_size={}, hop_size={})",
+                        window_size, hop_size
+                    )),
+                };
+            }
 
-    // Reconstruct audio for each component
-    let components: Vec<Vec<f64>> = component_specs
-        .iter()
-        .map(|spec| istft(spec, window_size as usize, hop_size as usize, samples.len()))
-        .collect();
+            let coefficients = mdct(&samples, window_size, hop_size);
+            let (component_coeffs, eigenvalues, variance_ratios) =
+                match pca_decompose_real(&coefficients, num_components as usize) {
+                    Ok(result) => result,
+                    Err(e) => {
+                        return PcaResultFFI {
+                            session: ptr::null_mut(),
+                            num_components: 0,
+                            sample_rate: 0,
+                            error: string_to_c(format!("PCA failed: {}", e)),
+                        };
+                    }
+                };
+
+            let components: Vec<Vec<f64>> = component_coeffs
+                .iter()
+                .map(|coeffs| imdct(coeffs, window_size, hop_size, samples.len()))
+                .collect();
+
+            finish_process_audio(components, eigenvalues, variance_ratios, sample_rate, window_size, hop_size)
+        }
+    }
+}
 
+/// Shared tail of `pca_process_audio`: wrap reconstructed components and
+/// PCA metadata into a session and the `PcaResultFFI` handed back to Swift.
+fn finish_process_audio(
+    components: Vec<Vec<f64>>,
+    eigenvalues: Vec<f64>,
+    variance_ratios: Vec<f64>,
+    sample_rate: u32,
+    window_size: usize,
+    hop_size: usize,
+) -> PcaResultFFI {
     let actual_components = components.len() as c_uint;
 
     let session = Box::new(PcaSession {
@@ -106,6 +186,8 @@ pub unsafe extern "C" fn pca_process_audio(
         eigenvalues,
         variance_ratios,
         sample_rate,
+        window_size,
+        hop_size,
     });
 
     PcaResultFFI {
@@ -148,6 +230,77 @@ pub unsafe extern "C" fn pca_get_component_info(
     }
 }
 
+/// Interpretable spectral/time-domain descriptors for a single component,
+/// the same family of features audio-analysis tools use to distinguish
+/// tonal/harmonic content from percussive/noisy content.
+#[repr(C)]
+pub struct ComponentFeaturesFFI {
+    pub rms: c_double,
+    pub zero_crossing_rate: c_double,
+    pub spectral_centroid: c_double,
+    pub spectral_rolloff: c_double,
+    pub spectral_flatness: c_double,
+    pub estimated_tempo_bpm: c_double,
+    pub error: *mut c_char,
+}
+
+/// Compute spectral/time-domain descriptors for a component, for automatic
+/// labeling (e.g. sorting components into "percussive" vs. "tonal" buckets
+/// in a mixer UI) instead of presenting bare component indices.
+///
+/// # Safety
+/// - `session` must be a valid session pointer
+/// - Caller must free the returned error (if non-null) with `pca_free_error`
+#[no_mangle]
+pub unsafe extern "C" fn pca_get_component_features(
+    session: *const PcaSession,
+    component_index: c_uint,
+) -> ComponentFeaturesFFI {
+    if session.is_null() {
+        return ComponentFeaturesFFI {
+            rms: 0.0,
+            zero_crossing_rate: 0.0,
+            spectral_centroid: 0.0,
+            spectral_rolloff: 0.0,
+            spectral_flatness: 0.0,
+            estimated_tempo_bpm: 0.0,
+            error: string_to_c("Session is null".to_string()),
+        };
+    }
+
+    let session = &*session;
+    let idx = component_index as usize;
+
+    if idx >= session.components.len() {
+        return ComponentFeaturesFFI {
+            rms: 0.0,
+            zero_crossing_rate: 0.0,
+            spectral_centroid: 0.0,
+            spectral_rolloff: 0.0,
+            spectral_flatness: 0.0,
+            estimated_tempo_bpm: 0.0,
+            error: string_to_c("Component index out of range".to_string()),
+        };
+    }
+
+    let features = compute_features(
+        &session.components[idx],
+        session.sample_rate,
+        session.window_size,
+        session.hop_size,
+    );
+
+    ComponentFeaturesFFI {
+        rms: features.rms,
+        zero_crossing_rate: features.zero_crossing_rate,
+        spectral_centroid: features.spectral_centroid,
+        spectral_rolloff: features.spectral_rolloff,
+        spectral_flatness: features.spectral_flatness,
+        estimated_tempo_bpm: features.estimated_tempo_bpm,
+        error: ptr::null_mut(),
+    }
+}
+
 /// Get audio samples for a specific component
 /// 
 /// # Safety
@@ -193,6 +346,77 @@ pub unsafe extern "C" fn pca_get_component_audio(
     }
 }
 
+/// Get a component's magnitude spectrogram in decibels, for visualization.
+///
+/// Recomputes `stft` on the stored time-domain component using the window
+/// and hop sizes from `pca_process_audio`, then converts each bin's
+/// magnitude to dB via `20*log10(max(|X|, eps))` (`eps = 1e-9`, to avoid
+/// `-inf` on silent bins). Returns a row-major matrix of `*out_frames` rows
+/// by `*out_bins` columns (`db[frame * bins + bin]`); `*out_sample_rate` is
+/// set so the host can label the time and frequency axes.
+///
+/// Returns null (and leaves the out-parameters untouched) on invalid input.
+///
+/// # Safety
+/// - `session` must be a valid session pointer
+/// - `out_frames` and `out_bins` must be valid pointers; `out_sample_rate`
+///   may be null if the caller doesn't need it
+/// - Caller must free the returned buffer with `pca_free_doubles`
+#[no_mangle]
+pub unsafe extern "C" fn pca_get_component_spectrogram(
+    session: *const PcaSession,
+    component_index: c_uint,
+    out_frames: *mut size_t,
+    out_bins: *mut size_t,
+    out_sample_rate: *mut c_uint,
+) -> *mut c_double {
+    if session.is_null() || out_frames.is_null() || out_bins.is_null() {
+        return ptr::null_mut();
+    }
+
+    let session = &*session;
+    let idx = component_index as usize;
+
+    if idx >= session.components.len() {
+        return ptr::null_mut();
+    }
+
+    const EPS: f64 = 1e-9;
+    let spectrogram = stft(&session.components[idx], session.window_size, session.hop_size);
+    let (num_bins, num_frames) = spectrogram.dim();
+
+    let mut db = Vec::with_capacity(num_bins * num_frames);
+    for frame_idx in 0..num_frames {
+        for bin_idx in 0..num_bins {
+            let magnitude = spectrogram[[bin_idx, frame_idx]].norm();
+            db.push(20.0 * magnitude.max(EPS).log10());
+        }
+    }
+
+    *out_frames = num_frames;
+    *out_bins = num_bins;
+    if !out_sample_rate.is_null() {
+        *out_sample_rate = session.sample_rate;
+    }
+
+    let mut buffer = db.into_boxed_slice();
+    let data = buffer.as_mut_ptr();
+    std::mem::forget(buffer);
+    data
+}
+
+/// Free a spectrogram buffer from `pca_get_component_spectrogram`
+///
+/// # Safety
+/// - `ptr` must be the return value of `pca_get_component_spectrogram` (with
+///   matching `len = *out_frames * *out_bins`) or null
+#[no_mangle]
+pub unsafe extern "C" fn pca_free_doubles(ptr: *mut c_double, len: size_t) {
+    if !ptr.is_null() {
+        let _ = Vec::from_raw_parts(ptr, len, len);
+    }
+}
+
 /// Mix components with given volumes and return audio buffer
 /// 
 /// # Safety
@@ -232,8 +456,201 @@ pub unsafe extern "C" fn pca_mix_components(
     }
 }
 
+/// Minimum number of STFT frames kept in a stream session's analysis
+/// buffer. A single-frame buffer makes `pca_decompose` degenerate (centering
+/// a one-column matrix zeroes it out, so every singular value and the
+/// reconstructed audio come out `NaN`), so the buffer always spans several
+/// hops' worth of history even when `n_components` is small.
+const MIN_STREAM_ANALYSIS_FRAMES: usize = 8;
+
+/// Opaque handle to a streaming session that incrementally decomposes audio
+/// fed in small chunks, for live preview over a microphone or file stream
+/// without re-running the whole decomposition on every new chunk.
+///
+/// Internally this keeps a sliding analysis buffer spanning several STFT
+/// frames (see [`MIN_STREAM_ANALYSIS_FRAMES`]). Every time exactly
+/// `hop_size` new samples have arrived, the buffer is advanced by one hop
+/// and `stft`/`pca_decompose`/`istft` are re-run over the whole buffer; the
+/// newest `hop_size` samples of each component's reconstruction (which
+/// `istft`'s own overlap-add has already settled) become the "latest hop".
+pub struct PcaStreamSession {
+    window_size: usize,
+    hop_size: usize,
+    n_components: usize,
+    sample_rate: u32,
+    /// Total length of `analysis`, spanning `MIN_STREAM_ANALYSIS_FRAMES`
+    /// (or more, if `n_components` requires it) STFT frames.
+    analysis_len: usize,
+    /// Sliding multi-frame analysis buffer, `analysis_len` samples long.
+    analysis: VecDeque<f64>,
+    /// Samples fed since the analysis buffer last advanced by a full hop.
+    incoming: VecDeque<f64>,
+    /// Finalized output of the most recently completed hop, per component.
+    latest_components: Vec<Vec<f64>>,
+}
+
+impl PcaStreamSession {
+    fn new(sample_rate: u32, n_components: usize, window_size: usize, hop_size: usize) -> Self {
+        let analysis_frames = (n_components + 1).max(MIN_STREAM_ANALYSIS_FRAMES);
+        let analysis_len = window_size + (analysis_frames - 1) * hop_size;
+        PcaStreamSession {
+            window_size,
+            hop_size,
+            n_components,
+            sample_rate,
+            analysis_len,
+            analysis: VecDeque::with_capacity(analysis_len),
+            incoming: VecDeque::with_capacity(hop_size),
+            latest_components: Vec::new(),
+        }
+    }
+
+    fn feed(&mut self, samples: &[f64]) {
+        for &sample in samples {
+            self.incoming.push_back(sample);
+            if self.incoming.len() < self.hop_size {
+                continue;
+            }
+
+            // Advance the analysis buffer by exactly one hop before
+            // re-running the decomposition, instead of processing the same
+            // window repeatedly.
+            for s in self.incoming.drain(..) {
+                if self.analysis.len() == self.analysis_len {
+                    self.analysis.pop_front();
+                }
+                self.analysis.push_back(s);
+            }
+
+            if self.analysis.len() == self.analysis_len {
+                self.process_hop();
+            }
+        }
+    }
+
+    fn process_hop(&mut self) {
+        let buffer: Vec<f64> = self.analysis.iter().copied().collect();
+        let spectrogram = stft(&buffer, self.window_size, self.hop_size);
+
+        let (component_specs, _eigenvalues, _variance_ratios) =
+            match pca_decompose(&spectrogram, self.n_components) {
+                Ok(result) => result,
+                Err(_) => return,
+            };
+
+        // Reconstruct each component over the whole analysis buffer and
+        // keep only the newest hop, which has already received every
+        // overlap-add contribution it ever will from istft's internal
+        // normalization.
+        let latest: Vec<Vec<f64>> = component_specs
+            .iter()
+            .map(|spec| {
+                let reconstructed = istft(spec, self.window_size, self.hop_size, self.analysis_len);
+                reconstructed[self.analysis_len - self.hop_size..].to_vec()
+            })
+            .collect();
+
+        self.latest_components = latest;
+    }
+}
+
+/// Create a new streaming session for incremental PCA mixing.
+///
+/// # Safety
+/// - Caller must free the returned session with `pca_stream_session_free`
+#[no_mangle]
+pub unsafe extern "C" fn pca_session_new_streaming(
+    sample_rate: c_uint,
+    num_components: c_uint,
+    window_size: c_uint,
+    hop_size: c_uint,
+) -> *mut PcaStreamSession {
+    Box::into_raw(Box::new(PcaStreamSession::new(
+        sample_rate,
+        num_components as usize,
+        window_size as usize,
+        hop_size as usize,
+    )))
+}
+
+/// Feed newly captured samples into a streaming session, running the
+/// decomposition for every hop that completes as a result once the
+/// analysis buffer has filled.
+///
+/// # Safety
+/// - `session` must be a valid pointer from `pca_session_new_streaming`
+/// - `data` must be a valid pointer to `len` doubles
+#[no_mangle]
+pub unsafe extern "C" fn pca_session_feed_samples(
+    session: *mut PcaStreamSession,
+    data: *const c_double,
+    len: size_t,
+) {
+    if session.is_null() || data.is_null() {
+        return;
+    }
+
+    let session = &mut *session;
+    let samples = slice::from_raw_parts(data, len);
+    session.feed(samples);
+}
+
+/// Mix the most recently completed hop's reconstructed components with the
+/// given volumes and return the resulting audio buffer.
+///
+/// Returns an empty buffer (length 0, no error) until the analysis buffer
+/// has filled and its first hop has been decomposed.
+///
+/// # Safety
+/// - `session` must be a valid pointer from `pca_session_new_streaming`
+/// - `volumes` must be a valid pointer to `num_volumes` doubles
+/// - Caller must free returned buffer with `pca_free_audio_buffer`
+#[no_mangle]
+pub unsafe extern "C" fn pca_session_render_latest(
+    session: *const PcaStreamSession,
+    volumes: *const c_double,
+    num_volumes: size_t,
+) -> AudioBufferFFI {
+    if session.is_null() || volumes.is_null() {
+        return AudioBufferFFI {
+            data: ptr::null_mut(),
+            length: 0,
+            sample_rate: 0,
+            error: string_to_c("Invalid parameters".to_string()),
+        };
+    }
+
+    let session = &*session;
+    let volumes_slice = slice::from_raw_parts(volumes, num_volumes);
+
+    let mixed = mix_components(&session.latest_components, volumes_slice);
+
+    let mut buffer = mixed.into_boxed_slice();
+    let data = buffer.as_mut_ptr();
+    let length = buffer.len();
+    std::mem::forget(buffer);
+
+    AudioBufferFFI {
+        data,
+        length,
+        sample_rate: session.sample_rate,
+        error: ptr::null_mut(),
+    }
+}
+
+/// Free a streaming PCA session
+///
+/// # Safety
+/// - `session` must be a valid pointer from `pca_session_new_streaming` or null
+#[no_mangle]
+pub unsafe extern "C" fn pca_stream_session_free(session: *mut PcaStreamSession) {
+    if !session.is_null() {
+        drop(Box::from_raw(session));
+    }
+}
+
 /// Encode audio samples as WAV data
-/// 
+///
 /// # Safety
 /// - `samples` must be a valid pointer to `num_samples` doubles
 /// - Caller must free returned data with `pca_free_bytes`
@@ -393,6 +810,70 @@ pub unsafe extern "C" fn pca_convert_to_wav(
     }
 }
 
+/// Interpolation mode for `pca_resample_buffer`, mirroring
+/// [`crate::resample::InterpolationMode`] as a C-compatible enum.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemixInterpMode {
+    Nearest = 0,
+    Linear = 1,
+    Cosine = 2,
+    Cubic = 3,
+    Polyphase = 4,
+}
+
+impl From<RemixInterpMode> for InterpolationMode {
+    fn from(mode: RemixInterpMode) -> Self {
+        match mode {
+            RemixInterpMode::Nearest => InterpolationMode::Nearest,
+            RemixInterpMode::Linear => InterpolationMode::Linear,
+            RemixInterpMode::Cosine => InterpolationMode::Cosine,
+            RemixInterpMode::Cubic => InterpolationMode::Cubic,
+            RemixInterpMode::Polyphase => InterpolationMode::Polyphase,
+        }
+    }
+}
+
+/// Resample a buffer of samples from `in_rate` to `out_rate` using the given
+/// interpolation mode, for retargeting component/mixed audio to a host's
+/// device rate.
+///
+/// # Safety
+/// - `data` must be a valid pointer to `len` doubles
+/// - Caller must free returned buffer with `pca_free_audio_buffer`
+#[no_mangle]
+pub unsafe extern "C" fn pca_resample_buffer(
+    data: *const c_double,
+    len: size_t,
+    in_rate: c_uint,
+    out_rate: c_uint,
+    mode: RemixInterpMode,
+) -> AudioBufferFFI {
+    if data.is_null() {
+        return AudioBufferFFI {
+            data: ptr::null_mut(),
+            length: 0,
+            sample_rate: 0,
+            error: string_to_c("Input data is null".to_string()),
+        };
+    }
+
+    let samples = slice::from_raw_parts(data, len);
+    let resampled = resample(samples, in_rate, out_rate, mode.into());
+
+    let mut buffer = resampled.into_boxed_slice();
+    let out_data = buffer.as_mut_ptr();
+    let length = buffer.len();
+    std::mem::forget(buffer);
+
+    AudioBufferFFI {
+        data: out_data,
+        length,
+        sample_rate: out_rate,
+        error: ptr::null_mut(),
+    }
+}
+
 // Helper to convert Rust string to C string
 fn string_to_c(s: String) -> *mut c_char {
     match std::ffi::CString::new(s) {
@@ -3,9 +3,17 @@
 //! Splits audio into principal component audio files by performing
 //! PCA/SVD on the spectrogram.
 
+pub mod channels;
+pub mod demucs;
+pub mod features;
 pub mod ffi;
+pub mod mdct;
+pub mod models;
+pub mod resample;
 
 use anyhow::{Context, Result, anyhow};
+use flacenc::bitsink::BitSink;
+use flacenc::component::BitRepr;
 use hound::{SampleFormat, WavReader, WavSpec};
 use ndarray::{Array2, Axis};
 use ndarray_linalg::SVD;
@@ -113,67 +121,83 @@ pub struct DecodedAudio {
 
 /// Load audio using symphonia (supports MP3, WAV, and other formats)
 pub fn load_audio_symphonia(data: &[u8]) -> Result<DecodedAudio> {
+    load_audio_symphonia_with_progress(data, |_bytes_decoded, _total_bytes| {})
+}
+
+/// Like [`load_audio_symphonia`], but calls `on_packet(bytes_decoded,
+/// total_bytes)` as each compressed packet is consumed, so a caller can
+/// surface decode progress for large uploads instead of blocking silently
+/// until the whole file is in memory.
+pub fn load_audio_symphonia_with_progress(
+    data: &[u8],
+    mut on_packet: impl FnMut(usize, usize),
+) -> Result<DecodedAudio> {
+    let total_bytes = data.len();
     let cursor = Cursor::new(data.to_vec());
     let mss = MediaSourceStream::new(Box::new(cursor), Default::default());
-    
+
     let hint = Hint::new();
     let format_opts = FormatOptions::default();
     let metadata_opts = MetadataOptions::default();
-    
+
     let probed = symphonia::default::get_probe()
         .format(&hint, mss, &format_opts, &metadata_opts)
         .context("Failed to probe audio format")?;
-    
+
     let mut format = probed.format;
-    
+
     let track = format.tracks()
         .iter()
         .find(|t| t.codec_params.codec != symphonia::core::codecs::CODEC_TYPE_NULL)
         .ok_or_else(|| anyhow!("No supported audio track found"))?;
-    
+
     let sample_rate = track.codec_params.sample_rate
         .ok_or_else(|| anyhow!("Unknown sample rate"))?;
     let channels = track.codec_params.channels
         .map(|c| c.count() as u16)
         .unwrap_or(2);
-    
+
     let decoder_opts = DecoderOptions::default();
     let mut decoder = symphonia::default::get_codecs()
         .make(&track.codec_params, &decoder_opts)
         .context("Failed to create audio decoder")?;
-    
+
     let track_id = track.id;
     let mut samples: Vec<f64> = Vec::new();
-    
+    let mut bytes_decoded = 0usize;
+
     loop {
         let packet = match format.next_packet() {
             Ok(p) => p,
-            Err(symphonia::core::errors::Error::IoError(e)) 
+            Err(symphonia::core::errors::Error::IoError(e))
                 if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
             Err(e) => return Err(e.into()),
         };
-        
+
         if packet.track_id() != track_id {
             continue;
         }
-        
+
+        bytes_decoded += packet.data.len();
+        on_packet(bytes_decoded, total_bytes);
+
         let decoded = match decoder.decode(&packet) {
             Ok(d) => d,
             Err(symphonia::core::errors::Error::DecodeError(_)) => continue,
             Err(e) => return Err(e.into()),
         };
-        
+
         let spec = *decoded.spec();
         let duration = decoded.capacity() as u64;
-        
+
         let mut sample_buf = SampleBuffer::<f32>::new(duration, spec);
         sample_buf.copy_interleaved_ref(decoded);
-        
+
         for &s in sample_buf.samples() {
             samples.push(s as f64);
         }
     }
-    
+
     // Convert to mono if stereo
     let mono_samples = if channels == 2 {
         samples.chunks(2)
@@ -224,6 +248,87 @@ pub fn load_audio_from_bytes(data: &[u8]) -> Result<(Vec<f64>, u32)> {
     }
 }
 
+/// Receives encoded audio bytes as they're produced, so the same mixing
+/// core can feed either a single in-memory buffer (for the base64/JSON
+/// response path) or a chunked HTTP body stream without ever holding two
+/// full copies of the encoded output at once.
+pub trait SampleSink {
+    fn write_chunk(&mut self, bytes: &[u8]) -> Result<()>;
+}
+
+impl SampleSink for Vec<u8> {
+    fn write_chunk(&mut self, bytes: &[u8]) -> Result<()> {
+        self.extend_from_slice(bytes);
+        Ok(())
+    }
+}
+
+/// Collects each chunk as a separate `Vec<u8>`, for callers that hand
+/// chunks off to a streaming HTTP body one at a time.
+impl SampleSink for Vec<Vec<u8>> {
+    fn write_chunk(&mut self, bytes: &[u8]) -> Result<()> {
+        self.push(bytes.to_vec());
+        Ok(())
+    }
+}
+
+/// Write `samples` as raw little-endian 32-bit float PCM (no container),
+/// in pieces of up to `chunk_frames` samples.
+pub fn write_pcm_chunked<S: SampleSink>(sink: &mut S, samples: &[f64], chunk_frames: usize) -> Result<()> {
+    for chunk in samples.chunks(chunk_frames.max(1)) {
+        let mut bytes = Vec::with_capacity(chunk.len() * 4);
+        for &s in chunk {
+            bytes.extend_from_slice(&(s as f32).to_le_bytes());
+        }
+        sink.write_chunk(&bytes)?;
+    }
+    Ok(())
+}
+
+/// Write `samples` as a 32-bit float mono WAV, in pieces of up to
+/// `chunk_frames` samples, to `sink`. Unlike [`encode_wav_to_bytes`], the
+/// header and each PCM chunk are handed to `sink` as they're produced
+/// rather than collected into one buffer first.
+pub fn write_wav_chunked<S: SampleSink>(
+    sink: &mut S,
+    samples: &[f64],
+    sample_rate: u32,
+    chunk_frames: usize,
+) -> Result<()> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 32,
+        sample_format: SampleFormat::Float,
+    };
+
+    let data_bytes = (samples.len() * 4) as u32;
+    let mut header = Vec::with_capacity(44);
+    write_wav_header(&mut header, &spec, data_bytes);
+    sink.write_chunk(&header)?;
+
+    write_pcm_chunked(sink, samples, chunk_frames)
+}
+
+fn write_wav_header(out: &mut Vec<u8>, spec: &WavSpec, data_bytes: u32) {
+    let byte_rate = spec.sample_rate * spec.channels as u32 * (spec.bits_per_sample as u32 / 8);
+    let block_align = spec.channels * (spec.bits_per_sample / 8);
+
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_bytes).to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&3u16.to_le_bytes()); // IEEE float
+    out.extend_from_slice(&spec.channels.to_le_bytes());
+    out.extend_from_slice(&spec.sample_rate.to_le_bytes());
+    out.extend_from_slice(&byte_rate.to_le_bytes());
+    out.extend_from_slice(&block_align.to_le_bytes());
+    out.extend_from_slice(&spec.bits_per_sample.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_bytes.to_le_bytes());
+}
+
 /// Encode audio samples as WAV bytes
 pub fn encode_wav_to_bytes(samples: &[f64], sample_rate: u32) -> Result<Vec<u8>> {
     let spec = WavSpec {
@@ -241,10 +346,95 @@ pub fn encode_wav_to_bytes(samples: &[f64], sample_rate: u32) -> Result<Vec<u8>>
         }
         writer.finalize()?;
     }
-    
+
     Ok(buffer.into_inner())
 }
 
+/// Encode mono audio samples as MP3 bytes at the given bitrate (kbps).
+pub fn encode_mp3_to_bytes(samples: &[f64], sample_rate: u32, bitrate_kbps: u32) -> Result<Vec<u8>> {
+    use mp3lame_encoder::{Bitrate, Builder, FlushNoGap, MonoPcm, Quality};
+
+    let pcm: Vec<i16> = samples
+        .iter()
+        .map(|&s| (s.clamp(-1.0, 1.0) * i16::MAX as f64).round() as i16)
+        .collect();
+
+    let mut builder = Builder::new().ok_or_else(|| anyhow!("Failed to create MP3 encoder"))?;
+    builder
+        .set_num_channels(1)
+        .map_err(|e| anyhow!("Failed to set MP3 channel count: {:?}", e))?;
+    builder
+        .set_sample_rate(sample_rate)
+        .map_err(|e| anyhow!("Failed to set MP3 sample rate: {:?}", e))?;
+    builder
+        .set_brate(nearest_mp3_bitrate(bitrate_kbps))
+        .map_err(|e| anyhow!("Failed to set MP3 bitrate: {:?}", e))?;
+    builder
+        .set_quality(Quality::Best)
+        .map_err(|e| anyhow!("Failed to set MP3 quality: {:?}", e))?;
+
+    let mut encoder = builder
+        .build()
+        .map_err(|e| anyhow!("Failed to build MP3 encoder: {:?}", e))?;
+
+    let mut output = Vec::new();
+    output.reserve(mp3lame_encoder::max_required_buffer_size(pcm.len()));
+
+    let encoded = encoder
+        .encode(MonoPcm(&pcm), output.spare_capacity_mut())
+        .map_err(|e| anyhow!("MP3 encoding failed: {:?}", e))?;
+    unsafe {
+        output.set_len(output.len() + encoded);
+    }
+
+    let flushed = encoder
+        .flush::<FlushNoGap>(output.spare_capacity_mut())
+        .map_err(|e| anyhow!("MP3 flush failed: {:?}", e))?;
+    unsafe {
+        output.set_len(output.len() + flushed);
+    }
+
+    Ok(output)
+}
+
+fn nearest_mp3_bitrate(kbps: u32) -> mp3lame_encoder::Bitrate {
+    use mp3lame_encoder::Bitrate::*;
+    const TABLE: &[(u32, mp3lame_encoder::Bitrate)] = &[
+        (96, Kbps96),
+        (128, Kbps128),
+        (160, Kbps160),
+        (192, Kbps192),
+        (224, Kbps224),
+        (256, Kbps256),
+        (320, Kbps320),
+    ];
+    TABLE
+        .iter()
+        .min_by_key(|(candidate, _)| (*candidate as i32 - kbps as i32).abs())
+        .map(|(_, bitrate)| *bitrate)
+        .unwrap_or(Kbps192)
+}
+
+/// Encode mono audio samples as lossless FLAC bytes.
+pub fn encode_flac_to_bytes(samples: &[f64], sample_rate: u32) -> Result<Vec<u8>> {
+    let pcm: Vec<i32> = samples
+        .iter()
+        .map(|&s| (s.clamp(-1.0, 1.0) * (i16::MAX as f64)).round() as i32)
+        .collect();
+
+    let config = flacenc::config::Encoder::default();
+    let source = flacenc::source::MemSource::from_samples(&pcm, 1, 16, sample_rate as usize);
+    let stream = flacenc::encode_with_fixed_block_size(&config, source, config.block_size)
+        .map_err(|e| anyhow!("FLAC encoding failed: {:?}", e))?;
+
+    let mut sink = flacenc::bitsink::ByteSink::new();
+    stream
+        .write(&mut sink)
+        .map_err(|e| anyhow!("FLAC bitstream write failed: {:?}", e))?;
+
+    Ok(sink.as_slice().to_vec())
+}
+
 /// Create a Hann window
 fn hann_window(size: usize) -> Vec<f64> {
     (0..size)
@@ -403,24 +593,208 @@ pub fn pca_decompose(
     Ok((components, eigenvalues, variance_ratios))
 }
 
-/// Process audio and return PCA components
-/// Supports WAV and MP3 formats (auto-detected)
-pub fn process_audio(
+/// Apply PCA directly to a matrix of real-valued coefficients (e.g. an MDCT
+/// transform) and return component coefficient matrices with metadata.
+///
+/// Unlike [`pca_decompose`], there is no magnitude/phase split: MDCT
+/// coefficients are already real and critically sampled, so each component
+/// is reconstructed straight from its SVD factors with no sign clamping,
+/// which avoids the phase-reuse artifacts `pca_decompose` depends on
+/// `griffin_lim_reconstruct` to clean up.
+pub fn pca_decompose_real(
+    coefficients: &Array2<f64>,
+    n_components: usize,
+) -> Result<(Vec<Array2<f64>>, Vec<f64>, Vec<f64>)> {
+    let (num_bins, num_frames) = coefficients.dim();
+
+    let mean = coefficients.mean_axis(Axis(1)).unwrap();
+    let mut centered = coefficients.clone();
+    for mut col in centered.columns_mut() {
+        col -= &mean;
+    }
+
+    let (u, s, vt) = centered.svd(true, true)
+        .context("SVD computation failed")?;
+
+    let u = u.unwrap();
+    let vt = vt.unwrap();
+
+    let n_components = n_components.min(s.len());
+    let total_variance: f64 = s.iter().map(|&x| x * x).sum();
+
+    let mut components = Vec::with_capacity(n_components);
+    let mut eigenvalues = Vec::with_capacity(n_components);
+    let mut variance_ratios = Vec::with_capacity(n_components);
+
+    for i in 0..n_components {
+        let eigenvalue = s[i] * s[i];
+        let variance_ratio = eigenvalue / total_variance * 100.0;
+
+        eigenvalues.push(eigenvalue);
+        variance_ratios.push(variance_ratio);
+
+        let u_col = u.column(i);
+        let v_row = vt.row(i);
+
+        let mut component = Array2::zeros((num_bins, num_frames));
+        for (bin_idx, &u_val) in u_col.iter().enumerate() {
+            for (frame_idx, &v_val) in v_row.iter().enumerate() {
+                component[[bin_idx, frame_idx]] = u_val * s[i] * v_val;
+            }
+        }
+
+        let scale = eigenvalue / total_variance;
+        for mut col in component.columns_mut() {
+            col += &(&mean * scale);
+        }
+
+        components.push(component);
+    }
+
+    Ok((components, eigenvalues, variance_ratios))
+}
+
+/// Refine a component's phase via Griffin-Lim iteration instead of reusing
+/// the original mixture phase directly.
+///
+/// `pca_decompose` only alters magnitude, so inverting a component spectrum
+/// with the mixture's original phase (`iterations == 0`) produces audible
+/// cross-talk and smearing once the magnitude no longer matches that phase.
+/// Each iteration re-estimates phase by inverting to the time domain,
+/// re-running `stft`, and keeping the new phase while restoring the fixed
+/// target `magnitude`; 20-60 iterations typically converge to a much
+/// cleaner reconstruction.
+pub fn griffin_lim_reconstruct(
+    magnitude: &Array2<f64>,
+    initial_phase: &Array2<f64>,
+    window_size: usize,
+    hop_size: usize,
+    output_length: usize,
+    iterations: usize,
+) -> Vec<f64> {
+    let mut spectrogram: Array2<Complex<f64>> = Array2::from_shape_fn(magnitude.dim(), |(i, j)| {
+        let mag = magnitude[[i, j]];
+        let phase = initial_phase[[i, j]];
+        Complex::new(mag * phase.cos(), mag * phase.sin())
+    });
+
+    for _ in 0..iterations {
+        let estimate = istft(&spectrogram, window_size, hop_size, output_length);
+        let re_stft = stft(&estimate, window_size, hop_size);
+        spectrogram = Array2::from_shape_fn(magnitude.dim(), |(i, j)| {
+            let mag = magnitude[[i, j]];
+            let phase = re_stft[[i, j]].arg();
+            Complex::new(mag * phase.cos(), mag * phase.sin())
+        });
+    }
+
+    istft(&spectrogram, window_size, hop_size, output_length)
+}
+
+/// A stage reached by [`process_audio_with_progress`], for reporting back
+/// on a long-running request (e.g. over Server-Sent Events) instead of
+/// blocking silently until the whole pipeline finishes.
+#[derive(Debug, Clone)]
+pub enum ProcessProgress {
+    /// A compressed packet was decoded; `bytes_decoded`/`total_bytes` are
+    /// counts of the compressed input, not decoded samples.
+    Decoding { bytes_decoded: usize, total_bytes: usize },
+    /// Decoding finished; the sample count and rate are now known.
+    Decoded { samples: usize, sample_rate: u32 },
+    /// The STFT has been computed.
+    ComputedStft { frames: usize },
+    /// SVD-based PCA decomposition is running.
+    RunningSvd,
+    /// One component has been reconstructed to the time domain via
+    /// `istft` (or Griffin-Lim refinement).
+    ReconstructedComponent { index: usize, total: usize },
+}
+
+/// Process audio and return PCA components, reporting progress stages to
+/// `on_progress` as they're reached. Supports WAV and MP3 formats
+/// (auto-detected).
+///
+/// If `target_sample_rate` is set and differs from the input's rate, the
+/// decoded samples are resampled to it (via `interpolation`) before the
+/// STFT, so both the returned components and `PcaResult::sample_rate`
+/// reflect the target rate.
+///
+/// `griffin_lim_iterations` controls phase reconstruction: `0` (the
+/// default) reuses the original mixture phase as before; a positive count
+/// runs that many Griffin-Lim refinement iterations per component instead
+/// (see [`griffin_lim_reconstruct`]).
+pub fn process_audio_with_progress(
     audio_data: &[u8],
     n_components: usize,
     window_size: usize,
     hop_size: usize,
+    target_sample_rate: Option<u32>,
+    interpolation: resample::InterpolationMode,
+    griffin_lim_iterations: usize,
+    mut on_progress: impl FnMut(ProcessProgress),
 ) -> Result<PcaResult> {
-    let (samples, sample_rate) = load_audio_from_bytes(audio_data)?;
-    
+    let total_bytes = audio_data.len();
+    let format = detect_format(audio_data);
+
+    let decode_with_progress = |on_progress: &mut dyn FnMut(ProcessProgress)| -> Result<DecodedAudio> {
+        load_audio_symphonia_with_progress(audio_data, |bytes_decoded, total| {
+            on_progress(ProcessProgress::Decoding { bytes_decoded, total_bytes: total });
+        })
+    };
+
+    let (samples, sample_rate) = match format {
+        AudioFormat::Wav => match decode_with_progress(&mut on_progress) {
+            Ok(decoded) => (decoded.samples, decoded.sample_rate),
+            Err(_) => {
+                let (samples, spec) = load_wav_from_bytes(audio_data)?;
+                on_progress(ProcessProgress::Decoding { bytes_decoded: total_bytes, total_bytes });
+                (samples, spec.sample_rate)
+            }
+        },
+        _ => {
+            let decoded = decode_with_progress(&mut on_progress)?;
+            (decoded.samples, decoded.sample_rate)
+        }
+    };
+    on_progress(ProcessProgress::Decoded { samples: samples.len(), sample_rate });
+
+    let (samples, sample_rate) = match target_sample_rate {
+        Some(target) if target != sample_rate => {
+            (resample::resample(&samples, sample_rate, target, interpolation), target)
+        }
+        _ => (samples, sample_rate),
+    };
+
     let spectrogram = stft(&samples, window_size, hop_size);
+    on_progress(ProcessProgress::ComputedStft { frames: spectrogram.ncols() });
+
+    on_progress(ProcessProgress::RunningSvd);
     let (component_specs, eigenvalues, variance_ratios) = pca_decompose(&spectrogram, n_components)?;
-    
+
+    let total_components = component_specs.len();
     let components: Vec<Vec<f64>> = component_specs
         .iter()
-        .map(|spec| istft(spec, window_size, hop_size, samples.len()))
+        .enumerate()
+        .map(|(index, spec)| {
+            let reconstructed = if griffin_lim_iterations == 0 {
+                istft(spec, window_size, hop_size, samples.len())
+            } else {
+                let magnitude = spec.mapv(|c| c.norm());
+                let phase = spec.mapv(|c| c.arg());
+                griffin_lim_reconstruct(
+                    &magnitude,
+                    &phase,
+                    window_size,
+                    hop_size,
+                    samples.len(),
+                    griffin_lim_iterations,
+                )
+            };
+            on_progress(ProcessProgress::ReconstructedComponent { index, total: total_components });
+            reconstructed
+        })
         .collect();
-    
+
     Ok(PcaResult {
         components,
         eigenvalues,
@@ -429,6 +803,29 @@ pub fn process_audio(
     })
 }
 
+/// Process audio and return PCA components
+/// Supports WAV and MP3 formats (auto-detected)
+pub fn process_audio(
+    audio_data: &[u8],
+    n_components: usize,
+    window_size: usize,
+    hop_size: usize,
+    target_sample_rate: Option<u32>,
+    interpolation: resample::InterpolationMode,
+    griffin_lim_iterations: usize,
+) -> Result<PcaResult> {
+    process_audio_with_progress(
+        audio_data,
+        n_components,
+        window_size,
+        hop_size,
+        target_sample_rate,
+        interpolation,
+        griffin_lim_iterations,
+        |_| {},
+    )
+}
+
 /// Mix multiple audio components with given volume levels
 pub fn mix_components(components: &[Vec<f64>], volumes: &[f64]) -> Vec<f64> {
     if components.is_empty() {
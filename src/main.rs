@@ -1,22 +1,105 @@
 use anyhow::{Context, Result};
 use axum::{
+    body::Body,
     extract::{DefaultBodyLimit, Multipart, State},
-    http::StatusCode,
-    response::{Html, IntoResponse, Json},
+    http::{header, StatusCode},
+    response::{
+        sse::{Event, Sse},
+        Html, IntoResponse, Json, Response,
+    },
     routing::{get, post},
     Router,
 };
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
 use clap::Parser;
+use futures::stream;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::convert::Infallible;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::services::ServeDir;
 use uuid::Uuid;
 
-use music_tool::{encode_wav_to_bytes, mix_components, process_audio, PcaResult};
+use music_tool::features::{compute_features, ComponentFeatures};
+use music_tool::resample::InterpolationMode;
+use music_tool::{
+    encode_flac_to_bytes, encode_mp3_to_bytes, encode_wav_to_bytes, mix_components, process_audio,
+    process_audio_with_progress, write_wav_chunked, PcaResult, ProcessProgress, SampleSink,
+};
+
+/// Number of pending progress events buffered between the blocking
+/// processing task and the SSE stream for `/api/process/stream`.
+const PROGRESS_CHANNEL_CAPACITY: usize = 32;
+
+/// Number of sample frames written to each chunk of a streamed mix response.
+const MIX_STREAM_CHUNK_FRAMES: usize = 4096;
+
+/// Number of encoded WAV chunks buffered between the blocking mix/encode
+/// task and the streamed HTTP body for `/api/mix/stream`.
+const MIX_STREAM_CHANNEL_CAPACITY: usize = 8;
+
+/// Feeds WAV chunks produced by `write_wav_chunked` straight to an mpsc
+/// channel as they're encoded, so the HTTP response body can stream them to
+/// the client instead of waiting for the whole file to be buffered first.
+struct ChannelSampleSink {
+    tx: mpsc::Sender<Vec<u8>>,
+}
+
+impl SampleSink for ChannelSampleSink {
+    fn write_chunk(&mut self, bytes: &[u8]) -> Result<()> {
+        self.tx
+            .blocking_send(bytes.to_vec())
+            .map_err(|_| anyhow::anyhow!("client disconnected"))
+    }
+}
+
+/// Default MP3 encoding bitrate, in kbps, for the `mp3` output format.
+const DEFAULT_MP3_BITRATE_KBPS: u32 = 192;
+
+/// Output container/codec for components and mixes returned by the API and
+/// CLI.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum OutputFormat {
+    #[default]
+    Wav,
+    Mp3,
+    Flac,
+}
+
+impl OutputFormat {
+    fn encode(&self, samples: &[f64], sample_rate: u32) -> Result<Vec<u8>> {
+        match self {
+            OutputFormat::Wav => encode_wav_to_bytes(samples, sample_rate),
+            OutputFormat::Mp3 => encode_mp3_to_bytes(samples, sample_rate, DEFAULT_MP3_BITRATE_KBPS),
+            OutputFormat::Flac => encode_flac_to_bytes(samples, sample_rate),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            OutputFormat::Wav => "wav",
+            OutputFormat::Mp3 => "mp3",
+            OutputFormat::Flac => "flac",
+        }
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "wav" => Ok(OutputFormat::Wav),
+            "mp3" => Ok(OutputFormat::Mp3),
+            "flac" => Ok(OutputFormat::Flac),
+            other => Err(format!("Unknown output format: {}", other)),
+        }
+    }
+}
 
 #[derive(Parser, Debug)]
 #[command(name = "music-tool")]
@@ -49,6 +132,24 @@ struct Args {
     /// Port for web server
     #[arg(short, long, default_value = "3000")]
     port: u16,
+
+    /// Output format for component files - CLI mode only (wav, mp3, flac)
+    #[arg(long, default_value = "wav")]
+    format: String,
+
+    /// Resample input to this rate (Hz) before analysis; defaults to the
+    /// input's native rate
+    #[arg(long)]
+    target_sample_rate: Option<u32>,
+
+    /// Interpolation mode used when resampling (nearest, linear, cosine, cubic, polyphase)
+    #[arg(long, default_value = "linear")]
+    resample_mode: String,
+
+    /// Griffin-Lim phase reconstruction iterations per component; 0 reuses
+    /// the original mixture phase (default, backward-compatible behavior)
+    #[arg(long, default_value = "0")]
+    griffin_lim_iterations: usize,
 }
 
 /// Stored session with processed audio components
@@ -71,7 +172,9 @@ struct ProcessResponse {
     eigenvalues: Vec<f64>,
     variance_ratios: Vec<f64>,
     sample_rate: u32,
-    /// Base64-encoded WAV for each component
+    /// Container/codec the components were encoded with (`wav`, `mp3`, `flac`)
+    format: String,
+    /// Base64-encoded audio for each component, encoded per `format`
     components: Vec<String>,
 }
 
@@ -79,19 +182,105 @@ struct ProcessResponse {
 struct MixRequest {
     session_id: String,
     volumes: Vec<f64>,
+    #[serde(default)]
+    format: OutputFormat,
 }
 
 #[derive(Serialize)]
 struct MixResponse {
-    /// Base64-encoded WAV of the mixed audio
+    /// Container/codec the mix was encoded with (`wav`, `mp3`, `flac`)
+    format: String,
+    /// Base64-encoded audio of the mixed output, encoded per `format`
     audio: String,
 }
 
+#[derive(Deserialize)]
+struct FeaturesRequest {
+    session_id: String,
+}
+
+#[derive(Serialize)]
+struct FeaturesResponse {
+    session_id: String,
+    features: Vec<ComponentFeatures>,
+}
+
 #[derive(Serialize)]
 struct ErrorResponse {
     error: String,
 }
 
+/// JSON payload carried by each `progress` SSE event from
+/// `/api/process/stream`; fields are populated according to which
+/// `ProcessProgress` stage was reached.
+#[derive(Serialize)]
+struct ProgressEventData {
+    stage: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bytes_decoded: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    total_bytes: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    samples: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample_rate: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frames: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component_index: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component_total: Option<usize>,
+}
+
+impl From<&ProcessProgress> for ProgressEventData {
+    fn from(progress: &ProcessProgress) -> Self {
+        let mut data = ProgressEventData {
+            stage: "",
+            bytes_decoded: None,
+            total_bytes: None,
+            samples: None,
+            sample_rate: None,
+            frames: None,
+            component_index: None,
+            component_total: None,
+        };
+
+        match *progress {
+            ProcessProgress::Decoding { bytes_decoded, total_bytes } => {
+                data.stage = "decoding";
+                data.bytes_decoded = Some(bytes_decoded);
+                data.total_bytes = Some(total_bytes);
+            }
+            ProcessProgress::Decoded { samples, sample_rate } => {
+                data.stage = "decoded";
+                data.samples = Some(samples);
+                data.sample_rate = Some(sample_rate);
+            }
+            ProcessProgress::ComputedStft { frames } => {
+                data.stage = "computed_stft";
+                data.frames = Some(frames);
+            }
+            ProcessProgress::RunningSvd => {
+                data.stage = "running_svd";
+            }
+            ProcessProgress::ReconstructedComponent { index, total } => {
+                data.stage = "reconstructed_component";
+                data.component_index = Some(index);
+                data.component_total = Some(total);
+            }
+        }
+
+        data
+    }
+}
+
+#[derive(Serialize)]
+struct ProcessStreamDone {
+    session_id: String,
+    num_components: usize,
+    sample_rate: u32,
+}
+
 async fn index() -> Html<&'static str> {
     Html(include_str!("../static/index.html"))
 }
@@ -102,10 +291,14 @@ async fn process_audio_handler(
 ) -> impl IntoResponse {
     let mut audio_data: Option<Vec<u8>> = None;
     let mut num_components: usize = 3;
+    let mut format = OutputFormat::default();
+    let mut target_sample_rate: Option<u32> = None;
+    let mut resample_mode = InterpolationMode::default();
+    let mut griffin_lim_iterations: usize = 0;
 
     while let Some(field) = multipart.next_field().await.ok().flatten() {
         let name = field.name().unwrap_or("").to_string();
-        
+
         match name.as_str() {
             "audio" => {
                 if let Ok(data) = field.bytes().await {
@@ -117,6 +310,26 @@ async fn process_audio_handler(
                     num_components = text.parse::<usize>().unwrap_or(3);
                 }
             }
+            "format" => {
+                if let Ok(text) = field.text().await {
+                    format = text.parse().unwrap_or_default();
+                }
+            }
+            "target_sample_rate" => {
+                if let Ok(text) = field.text().await {
+                    target_sample_rate = text.parse::<u32>().ok();
+                }
+            }
+            "resample_mode" => {
+                if let Ok(text) = field.text().await {
+                    resample_mode = text.parse().unwrap_or_default();
+                }
+            }
+            "griffin_lim_iterations" => {
+                if let Ok(text) = field.text().await {
+                    griffin_lim_iterations = text.parse::<usize>().unwrap_or(0);
+                }
+            }
             _ => {}
         }
     }
@@ -135,7 +348,15 @@ async fn process_audio_handler(
     };
 
     // Process the audio
-    let result = match process_audio(&audio_data, num_components, state.window_size, state.hop_size) {
+    let result = match process_audio(
+        &audio_data,
+        num_components,
+        state.window_size,
+        state.hop_size,
+        target_sample_rate,
+        resample_mode,
+        griffin_lim_iterations,
+    ) {
         Ok(r) => r,
         Err(e) => {
             return (
@@ -148,12 +369,12 @@ async fn process_audio_handler(
         }
     };
 
-    // Encode each component as base64 WAV
+    // Encode each component as base64, in the requested format
     let mut encoded_components = Vec::new();
     for component in &result.components {
-        match encode_wav_to_bytes(component, result.sample_rate) {
-            Ok(wav_bytes) => {
-                encoded_components.push(BASE64.encode(&wav_bytes));
+        match format.encode(component, result.sample_rate) {
+            Ok(bytes) => {
+                encoded_components.push(BASE64.encode(&bytes));
             }
             Err(e) => {
                 return (
@@ -189,11 +410,150 @@ async fn process_audio_handler(
         eigenvalues: result_ref.eigenvalues.clone(),
         variance_ratios: result_ref.variance_ratios.clone(),
         sample_rate: result_ref.sample_rate,
+        format: format.as_str().to_string(),
         components: encoded_components,
     })
     .into_response()
 }
 
+/// Like `process_audio_handler`, but streams processing progress as
+/// Server-Sent Events instead of blocking silently until a 100MB upload
+/// finishes decoding and analyzing. The `Session` is only created once
+/// processing completes, reusing the same `PcaResult` computed for the
+/// final `done` event.
+async fn process_stream_handler(
+    State(state): State<Arc<AppState>>,
+    mut multipart: Multipart,
+) -> Sse<impl futures::Stream<Item = Result<Event, Infallible>>> {
+    let mut audio_data: Option<Vec<u8>> = None;
+    let mut num_components: usize = 3;
+    let mut target_sample_rate: Option<u32> = None;
+    let mut resample_mode = InterpolationMode::default();
+    let mut griffin_lim_iterations: usize = 0;
+
+    while let Some(field) = multipart.next_field().await.ok().flatten() {
+        let name = field.name().unwrap_or("").to_string();
+
+        match name.as_str() {
+            "audio" => {
+                if let Ok(data) = field.bytes().await {
+                    audio_data = Some(data.to_vec());
+                }
+            }
+            "num_components" => {
+                if let Ok(text) = field.text().await {
+                    num_components = text.parse::<usize>().unwrap_or(3);
+                }
+            }
+            "target_sample_rate" => {
+                if let Ok(text) = field.text().await {
+                    target_sample_rate = text.parse::<u32>().ok();
+                }
+            }
+            "resample_mode" => {
+                if let Ok(text) = field.text().await {
+                    resample_mode = text.parse().unwrap_or_default();
+                }
+            }
+            "griffin_lim_iterations" => {
+                if let Ok(text) = field.text().await {
+                    griffin_lim_iterations = text.parse::<usize>().unwrap_or(0);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let (tx, rx) = mpsc::channel::<ProcessProgress>(PROGRESS_CHANNEL_CAPACITY);
+    let window_size = state.window_size;
+    let hop_size = state.hop_size;
+
+    let handle = match audio_data {
+        Some(audio_data) => Some(tokio::task::spawn_blocking(move || {
+            process_audio_with_progress(
+                &audio_data,
+                num_components,
+                window_size,
+                hop_size,
+                target_sample_rate,
+                resample_mode,
+                griffin_lim_iterations,
+                move |progress| {
+                    let _ = tx.blocking_send(progress);
+                },
+            )
+        })),
+        None => None,
+    };
+
+    enum StreamState {
+        Streaming {
+            rx: mpsc::Receiver<ProcessProgress>,
+            handle: tokio::task::JoinHandle<Result<PcaResult>>,
+            state: Arc<AppState>,
+        },
+        MissingAudio,
+        Finished,
+    }
+
+    let initial = match handle {
+        Some(handle) => StreamState::Streaming { rx, handle, state },
+        None => StreamState::MissingAudio,
+    };
+
+    let event_stream = stream::unfold(initial, |s| async move {
+        match s {
+            StreamState::MissingAudio => {
+                let event = Event::default().event("error").json_data(ErrorResponse {
+                    error: "No audio file provided".to_string(),
+                });
+                Some((Ok(event.unwrap()), StreamState::Finished))
+            }
+            StreamState::Streaming { mut rx, handle, state } => {
+                if let Some(progress) = rx.recv().await {
+                    let event = Event::default()
+                        .event("progress")
+                        .json_data(ProgressEventData::from(&progress))
+                        .unwrap();
+                    Some((Ok(event), StreamState::Streaming { rx, handle, state }))
+                } else {
+                    let event = match handle.await {
+                        Ok(Ok(result)) => {
+                            let session_id = Uuid::new_v4().to_string();
+                            let done = ProcessStreamDone {
+                                session_id: session_id.clone(),
+                                num_components: result.components.len(),
+                                sample_rate: result.sample_rate,
+                            };
+                            let session = Session {
+                                result: Arc::new(result),
+                            };
+                            state.sessions.lock().unwrap().insert(session_id, session);
+                            Event::default().event("done").json_data(done).unwrap()
+                        }
+                        Ok(Err(e)) => Event::default()
+                            .event("error")
+                            .json_data(ErrorResponse {
+                                error: format!("Processing failed: {}", e),
+                            })
+                            .unwrap(),
+                        Err(e) => Event::default()
+                            .event("error")
+                            .json_data(ErrorResponse {
+                                error: format!("Processing task failed: {}", e),
+                            })
+                            .unwrap(),
+                    };
+                    Some((Ok(event), StreamState::Finished))
+                }
+            }
+            StreamState::Finished => None,
+        }
+    });
+
+    Sse::new(event_stream)
+}
+
 async fn mix_audio_handler(
     State(state): State<Arc<AppState>>,
     Json(request): Json<MixRequest>,
@@ -219,10 +579,11 @@ async fn mix_audio_handler(
     // Mix the components
     let mixed = mix_components(&session.result.components, &request.volumes);
 
-    // Encode as WAV
-    match encode_wav_to_bytes(&mixed, session.result.sample_rate) {
-        Ok(wav_bytes) => Json(MixResponse {
-            audio: BASE64.encode(&wav_bytes),
+    // Encode in the requested format
+    match request.format.encode(&mixed, session.result.sample_rate) {
+        Ok(bytes) => Json(MixResponse {
+            format: request.format.as_str().to_string(),
+            audio: BASE64.encode(&bytes),
         })
         .into_response(),
         Err(e) => (
@@ -235,6 +596,94 @@ async fn mix_audio_handler(
     }
 }
 
+/// Like `mix_audio_handler`, but streams the mixed audio to the client as
+/// WAV-framed chunks instead of buffering the whole file, base64-encoding
+/// it, and wrapping it in one JSON response. The mix/encode work runs on a
+/// blocking task that feeds each encoded chunk to the response body over a
+/// channel as soon as it's produced, so the body never holds a second full
+/// copy of the encoded track the way collecting into a `Vec` first would.
+async fn mix_stream_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<MixRequest>,
+) -> impl IntoResponse {
+    let session = {
+        let sessions = state.sessions.lock().unwrap();
+        sessions.get(&request.session_id).cloned()
+    };
+
+    let session = match session {
+        Some(s) => s,
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "Session not found".to_string(),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let (tx, rx) = mpsc::channel::<Vec<u8>>(MIX_STREAM_CHANNEL_CAPACITY);
+    let result = session.result.clone();
+    let volumes = request.volumes.clone();
+
+    tokio::task::spawn_blocking(move || {
+        let mixed = mix_components(&result.components, &volumes);
+        let mut sink = ChannelSampleSink { tx };
+        write_wav_chunked(&mut sink, &mixed, result.sample_rate, MIX_STREAM_CHUNK_FRAMES)
+    });
+
+    // Each chunk is sent to `tx` as soon as it's encoded, so the body
+    // stream yields them to the client as they arrive instead of waiting
+    // for the whole mix to be buffered.
+    let body = Body::from_stream(stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|chunk| (Ok::<_, std::io::Error>(chunk), rx))
+    }));
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "audio/wav")
+        .body(body)
+        .unwrap()
+        .into_response()
+}
+
+async fn features_handler(
+    State(state): State<Arc<AppState>>,
+    Json(request): Json<FeaturesRequest>,
+) -> impl IntoResponse {
+    let session = {
+        let sessions = state.sessions.lock().unwrap();
+        sessions.get(&request.session_id).cloned()
+    };
+
+    let session = match session {
+        Some(s) => s,
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    error: "Session not found".to_string(),
+                }),
+            )
+                .into_response()
+        }
+    };
+
+    let features: Vec<ComponentFeatures> = session
+        .result
+        .components
+        .iter()
+        .map(|component| compute_features(component, session.result.sample_rate, state.window_size, state.hop_size))
+        .collect();
+
+    Json(FeaturesResponse {
+        session_id: request.session_id,
+        features,
+    })
+    .into_response()
+}
+
 async fn run_server(args: Args) -> Result<()> {
     let state = Arc::new(AppState {
         sessions: Mutex::new(HashMap::new()),
@@ -250,7 +699,10 @@ async fn run_server(args: Args) -> Result<()> {
     let app = Router::new()
         .route("/", get(index))
         .route("/api/process", post(process_audio_handler))
+        .route("/api/process/stream", post(process_stream_handler))
         .route("/api/mix", post(mix_audio_handler))
+        .route("/api/mix/stream", post(mix_stream_handler))
+        .route("/api/features", post(features_handler))
         .nest_service("/static", ServeDir::new("static"))
         .layer(DefaultBodyLimit::max(100 * 1024 * 1024)) // 100MB limit
         .layer(cors)
@@ -266,45 +718,55 @@ async fn run_server(args: Args) -> Result<()> {
 }
 
 fn run_cli(args: Args) -> Result<()> {
-    use hound::{SampleFormat, WavSpec, WavWriter};
+    let format: OutputFormat = args
+        .format
+        .parse()
+        .map_err(|e: String| anyhow::anyhow!(e))?;
+    let resample_mode: InterpolationMode = args
+        .resample_mode
+        .parse()
+        .map_err(|e: String| anyhow::anyhow!(e))?;
 
     let input = args.input.context("Input file required in CLI mode")?;
-    
+
     println!("Loading audio file: {:?}", input);
     let audio_data = std::fs::read(&input)?;
-    
+
     println!("Processing with {} components...", args.num_components);
-    let result = process_audio(&audio_data, args.num_components, args.window_size, args.hop_size)?;
-    
+    let result = process_audio(
+        &audio_data,
+        args.num_components,
+        args.window_size,
+        args.hop_size,
+        args.target_sample_rate,
+        resample_mode,
+        args.griffin_lim_iterations,
+    )?;
+
     println!("Eigenvalues for top {} components:", result.components.len());
     for (i, (ev, vr)) in result.eigenvalues.iter().zip(result.variance_ratios.iter()).enumerate() {
         println!("  Component {}: eigenvalue = {:.4}, variance = {:.2}%", i + 1, ev, vr);
     }
-    
+
     std::fs::create_dir_all(&args.output_dir)?;
-    
+
     let input_stem = input.file_stem()
         .and_then(|s| s.to_str())
         .unwrap_or("audio");
-    
+
     for (i, component) in result.components.iter().enumerate() {
-        let output_path = args.output_dir.join(format!("{}_component_{}.wav", input_stem, i + 1));
+        let output_path = args.output_dir.join(format!(
+            "{}_component_{}.{}",
+            input_stem,
+            i + 1,
+            format.as_str()
+        ));
         println!("Saving: {:?}", output_path);
-        
-        let spec = WavSpec {
-            channels: 1,
-            sample_rate: result.sample_rate,
-            bits_per_sample: 32,
-            sample_format: SampleFormat::Float,
-        };
-        
-        let mut writer = WavWriter::create(&output_path, spec)?;
-        for &sample in component {
-            writer.write_sample(sample as f32)?;
-        }
-        writer.finalize()?;
+
+        let encoded = format.encode(component, result.sample_rate)?;
+        std::fs::write(&output_path, encoded)?;
     }
-    
+
     println!("Done! Extracted {} principal components.", result.components.len());
     Ok(())
 }
@@ -0,0 +1,165 @@
+//! Modified Discrete Cosine Transform (MDCT) analysis/synthesis, a
+//! critically-sampled alternative to [`crate::stft`]/[`crate::istft`]: each
+//! 2N-sample, 50%-overlapped frame maps to N *real* coefficients instead of
+//! N complex bins, so PCA run on MDCT coefficients operates on half the
+//! feature count and component reconstruction never has to discard or
+//! reuse phase.
+//!
+//! Both directions are computed with a single `window_size`-point FFT via
+//! pre/post-twiddle, the same trick [`crate::stft`]/[`crate::istft`] use to
+//! turn the windowed time-domain frame into bins with one `rustfft` call,
+//! rather than the O(N^2) direct summation of the defining formula.
+
+use ndarray::Array2;
+use rustfft::{num_complex::Complex as FftComplex, FftPlanner};
+use std::f64::consts::PI;
+
+/// Sine window satisfying the Princen-Bradley condition
+/// `w[n]^2 + w[n + window_size/2]^2 = 1`, required for time-domain alias
+/// cancellation when overlap-adding [`imdct`] output across frames.
+fn mdct_window(window_size: usize) -> Vec<f64> {
+    (0..window_size)
+        .map(|i| (PI / window_size as f64 * (i as f64 + 0.5)).sin())
+        .collect()
+}
+
+/// Compute the MDCT of `samples`, producing `window_size / 2` real
+/// coefficients per frame. Frames are `window_size` samples wide; for
+/// correct time-domain alias cancellation on the way back through
+/// [`imdct`], `hop_size` must be `window_size / 2`.
+pub fn mdct(samples: &[f64], window_size: usize, hop_size: usize) -> Array2<f64> {
+    let window = mdct_window(window_size);
+    let n = window_size / 2;
+    let angle_step = PI / n as f64;
+    let c = n as f64 / 2.0 + 0.5;
+
+    let num_frames = (samples.len().saturating_sub(window_size)) / hop_size + 1;
+
+    let mut planner = FftPlanner::new();
+    let ifft = planner.plan_fft_inverse(window_size);
+
+    let mut coefficients = Array2::zeros((n, num_frames));
+
+    for (frame_idx, start) in (0..samples.len().saturating_sub(window_size - 1))
+        .step_by(hop_size)
+        .enumerate()
+    {
+        if frame_idx >= num_frames {
+            break;
+        }
+
+        // Pre-twiddle: fold the windowed real frame into a complex sequence
+        // so a single window_size-point FFT yields every output bin.
+        let mut buffer: Vec<FftComplex<f64>> = samples[start..start + window_size]
+            .iter()
+            .zip(window.iter())
+            .enumerate()
+            .map(|(i, (&s, &w))| {
+                let phase = 0.5 * angle_step * (i as f64 + c);
+                let y = s * w;
+                FftComplex::new(y * phase.cos(), y * phase.sin())
+            })
+            .collect();
+
+        ifft.process(&mut buffer);
+
+        // Post-twiddle: only the first N bins are needed, and only their
+        // real part, since the MDCT coefficients are real.
+        for k in 0..n {
+            let phase = angle_step * c * k as f64;
+            let twiddle = FftComplex::new(phase.cos(), phase.sin());
+            coefficients[[k, frame_idx]] = (buffer[k] * twiddle).re;
+        }
+    }
+
+    coefficients
+}
+
+/// Inverse MDCT: reconstructs a time-domain signal of `output_length`
+/// samples from `coefficients` by windowing and overlap-adding each frame's
+/// synthesis output. Requires `hop_size == window_size / 2` (the same
+/// framing used by [`mdct`]) for the overlap-add to cancel aliasing.
+pub fn imdct(
+    coefficients: &Array2<f64>,
+    window_size: usize,
+    hop_size: usize,
+    output_length: usize,
+) -> Vec<f64> {
+    let window = mdct_window(window_size);
+    let n = window_size / 2;
+    let angle_step = PI / n as f64;
+    let c = n as f64 / 2.0 + 0.5;
+    let scale = 2.0 / n as f64;
+
+    let mut planner = FftPlanner::new();
+    let ifft = planner.plan_fft_inverse(window_size);
+
+    let mut output = vec![0.0; output_length];
+
+    for frame_idx in 0..coefficients.ncols() {
+        let start = frame_idx * hop_size;
+        if start >= output_length {
+            break;
+        }
+
+        // Zero-pad to window_size and pre-twiddle in the frequency domain,
+        // the adjoint of mdct's pre/post-twiddle pair.
+        let mut buffer = vec![FftComplex::new(0.0, 0.0); window_size];
+        for k in 0..n {
+            let phase = angle_step * c * k as f64;
+            let twiddle = FftComplex::new(phase.cos(), phase.sin());
+            buffer[k] = FftComplex::new(coefficients[[k, frame_idx]], 0.0) * twiddle;
+        }
+
+        ifft.process(&mut buffer);
+
+        for (i, &val) in buffer.iter().enumerate() {
+            if start + i >= output_length {
+                break;
+            }
+            let phase = 0.5 * angle_step * (i as f64 + c);
+            let rotated = val * FftComplex::new(phase.cos(), phase.sin());
+            output[start + i] += window[i] * scale * rotated.re;
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn window_satisfies_princen_bradley_condition() {
+        let window_size = 64;
+        let n = window_size / 2;
+        let window = mdct_window(window_size);
+        for i in 0..n {
+            let sum = window[i] * window[i] + window[i + n] * window[i + n];
+            assert!((sum - 1.0).abs() < 1e-9, "index {i}: {sum}");
+        }
+    }
+
+    #[test]
+    fn forward_then_inverse_reconstructs_sine_wave() {
+        let window_size = 256;
+        let hop_size = window_size / 2;
+        let sample_rate = 44100.0;
+        let samples: Vec<f64> = (0..window_size * 6)
+            .map(|i| (2.0 * PI * 220.0 * i as f64 / sample_rate).sin())
+            .collect();
+
+        let coefficients = mdct(&samples, window_size, hop_size);
+        let reconstructed = imdct(&coefficients, window_size, hop_size, samples.len());
+
+        // Skip a full window at each end; the outer half-window there is
+        // only partially covered by overlap-add at the reconstruction's edges.
+        let margin = window_size;
+        let error: f64 = (margin..samples.len() - margin)
+            .map(|i| (samples[i] - reconstructed[i]).abs())
+            .sum::<f64>()
+            / (samples.len() - 2 * margin) as f64;
+        assert!(error < 1e-6, "mean reconstruction error too high: {error}");
+    }
+}
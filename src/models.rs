@@ -0,0 +1,45 @@
+//! Registry of downloadable Demucs ONNX models.
+//!
+//! Centralizes per-model metadata (mirrors, checksum, stem layout) so both
+//! the downloader binary and the separation module can drive their
+//! assumptions from the selected model instead of hardcoding a single
+//! `htdemucs_6s` entry.
+
+/// Metadata for one downloadable separation model.
+pub struct ModelInfo {
+    /// Short identifier used on the command line and in output paths.
+    pub name: &'static str,
+    /// Mirror URLs to try in order; later ones are used only if earlier
+    /// ones fail.
+    pub mirrors: &'static [&'static str],
+    /// Expected SHA-256 of the completed download, or `""` to skip
+    /// verification.
+    pub sha256: &'static str,
+    /// Approximate download size, for progress/usage output.
+    pub size_mb: u64,
+    /// Stem names in the order the model outputs them.
+    pub stem_names: &'static [&'static str],
+    /// Number of audio channels the model expects/produces per stem.
+    pub output_channels: usize,
+}
+
+/// Available separation models.
+pub const MODELS: &[ModelInfo] = &[ModelInfo {
+    name: "htdemucs_6s",
+    // UVR (Ultimate Vocal Remover) provides ONNX models for various separators;
+    // this is the 6-stem Demucs model converted to ONNX. The facefusion-assets
+    // mirror is tried first, falling back to the Hugging Face copy.
+    mirrors: &[
+        "https://github.com/facefusion/facefusion-assets/releases/download/models-3.0.0/demucs_htdemucs_6s.onnx",
+        "https://huggingface.co/facefusion/models-3.0.0/resolve/main/demucs_htdemucs_6s.onnx",
+    ],
+    sha256: "", // Empty means skip verification (model may vary)
+    size_mb: 85,
+    stem_names: &["drums", "bass", "vocals", "guitar", "piano", "other"],
+    output_channels: 2,
+}];
+
+/// Look up a model by name.
+pub fn find_model(name: &str) -> Option<&'static ModelInfo> {
+    MODELS.iter().find(|m| m.name == name)
+}
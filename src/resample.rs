@@ -0,0 +1,363 @@
+//! Arbitrary-ratio fractional resampling via a windowed-sinc polyphase
+//! filter, for use where rubato's fixed-input constructors don't fit (e.g.
+//! resampling back to a source rate that isn't known until after
+//! separation).
+
+use ndarray::{Array2, ArrayView2};
+
+/// Number of sinc taps on each side of the center; the filter spans
+/// `ORDER * 2` taps.
+const DEFAULT_ORDER: usize = 16;
+
+/// Number of precomputed fractional phases per integer sample step.
+const DEFAULT_SUBPHASES: usize = 256;
+
+/// Kaiser window beta controlling sidelobe suppression vs. main-lobe width.
+const KAISER_BETA: f64 = 8.0;
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+/// Zeroth-order modified Bessel function of the first kind, via its power
+/// series, accurate enough for Kaiser window coefficients.
+fn bessel_i0(x: f64) -> f64 {
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    let mut k = 1.0;
+    loop {
+        term *= (x * x / 4.0) / (k * k);
+        if term < 1e-10 {
+            break;
+        }
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+fn kaiser(n: f64, half_width: f64, beta: f64) -> f64 {
+    let r = (n / half_width).clamp(-1.0, 1.0);
+    bessel_i0(beta * (1.0 - r * r).sqrt()) / bessel_i0(beta)
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-12 {
+        1.0
+    } else {
+        (std::f64::consts::PI * x).sin() / (std::f64::consts::PI * x)
+    }
+}
+
+/// A fractional-ratio resampler backed by a precomputed polyphase
+/// windowed-sinc filter bank.
+pub struct SincResampler {
+    order: usize,
+    num: usize,
+    den: usize,
+    /// `table[phase]` holds `order * 2` taps for fractional offset
+    /// `phase / subphases`.
+    table: Vec<Vec<f64>>,
+    subphases: usize,
+}
+
+impl SincResampler {
+    /// Build a resampler converting `in_rate` to `out_rate`.
+    pub fn new(in_rate: u32, out_rate: u32) -> Self {
+        Self::with_order(in_rate, out_rate, DEFAULT_ORDER, DEFAULT_SUBPHASES)
+    }
+
+    /// Build a resampler with an explicit filter order (taps per side) and
+    /// number of precomputed fractional phases.
+    pub fn with_order(in_rate: u32, out_rate: u32, order: usize, subphases: usize) -> Self {
+        let g = gcd(in_rate as usize, out_rate as usize).max(1);
+        let num = in_rate as usize / g;
+        let den = out_rate as usize / g;
+
+        let taps = order * 2;
+        let half_width = order as f64;
+        let mut table = Vec::with_capacity(subphases);
+
+        for phase in 0..subphases {
+            let frac = phase as f64 / subphases as f64;
+            let mut row = Vec::with_capacity(taps);
+            let mut sum = 0.0;
+            for t in 0..taps {
+                // Tap t samples from the center at offset (t - order + 1) - frac.
+                let n = (t as f64) - (order as f64) + 1.0 - frac;
+                let w = kaiser(n, half_width, KAISER_BETA);
+                let s = sinc(n) * w;
+                row.push(s);
+                sum += s;
+            }
+            if sum.abs() > 1e-12 {
+                for s in row.iter_mut() {
+                    *s /= sum;
+                }
+            }
+            table.push(row);
+        }
+
+        Self { order, num, den, table, subphases }
+    }
+
+    /// Resample a single channel of samples.
+    pub fn process(&self, input: &[f32]) -> Vec<f32> {
+        if input.is_empty() || self.num == self.den {
+            return input.to_vec();
+        }
+
+        let out_len = (input.len() * self.den) / self.num;
+        let mut output = Vec::with_capacity(out_len);
+
+        let mut ipos: usize = 0;
+        let mut frac: usize = 0;
+
+        for _ in 0..out_len {
+            let phase = (frac * self.subphases) / self.den;
+            let taps = &self.table[phase.min(self.subphases - 1)];
+
+            let mut acc = 0.0f64;
+            for (t, &coef) in taps.iter().enumerate() {
+                let src_idx = ipos as isize + t as isize - self.order as isize + 1;
+                if src_idx >= 0 && (src_idx as usize) < input.len() {
+                    acc += input[src_idx as usize] as f64 * coef;
+                }
+            }
+            output.push(acc as f32);
+
+            frac += self.num;
+            while frac >= self.den {
+                frac -= self.den;
+                ipos += 1;
+            }
+        }
+
+        output
+    }
+}
+
+/// Interpolation strategy used by [`resample`] to convert a single-channel
+/// `f64` signal between sample rates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum InterpolationMode {
+    /// Pick the nearest source sample; fastest, lowest quality.
+    Nearest,
+    /// Linear interpolation between the two surrounding samples.
+    #[default]
+    Linear,
+    /// Cosine-eased interpolation; smoother transitions than linear.
+    Cosine,
+    /// Catmull-Rom cubic interpolation over the four surrounding samples.
+    Cubic,
+    /// Band-limited windowed-sinc convolution; highest quality, slowest.
+    Polyphase,
+}
+
+impl std::str::FromStr for InterpolationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "nearest" => Ok(InterpolationMode::Nearest),
+            "linear" => Ok(InterpolationMode::Linear),
+            "cosine" => Ok(InterpolationMode::Cosine),
+            "cubic" => Ok(InterpolationMode::Cubic),
+            "polyphase" => Ok(InterpolationMode::Polyphase),
+            other => Err(format!("Unknown interpolation mode: {}", other)),
+        }
+    }
+}
+
+fn sample_at(samples: &[f64], index: isize) -> f64 {
+    let last = samples.len() as isize - 1;
+    samples[index.clamp(0, last) as usize]
+}
+
+/// Number of sinc taps on each side of the center for [`InterpolationMode::Polyphase`].
+const POLYPHASE_HALF_WIDTH: isize = 8;
+
+/// Number of precomputed fractional sub-phases for [`InterpolationMode::Polyphase`].
+const POLYPHASE_SUBPHASES: usize = 256;
+
+/// Build a bank of `POLYPHASE_SUBPHASES + 1` Hann-windowed sinc sub-filters,
+/// one per quantized fraction, each spanning `POLYPHASE_HALF_WIDTH * 2 + 1`
+/// taps. Each row is normalized to sum to 1.0 (matching [`SincResampler`]),
+/// since the windowed-sinc taps otherwise only sum to ~1 and a DC input
+/// would drift by the leftover gain error at every non-integer phase.
+fn polyphase_filter_bank() -> Vec<Vec<f64>> {
+    (0..=POLYPHASE_SUBPHASES)
+        .map(|i| {
+            let frac = i as f64 / POLYPHASE_SUBPHASES as f64;
+            let mut row: Vec<f64> = (-POLYPHASE_HALF_WIDTH..=POLYPHASE_HALF_WIDTH)
+                .map(|k| {
+                    let x = k as f64 - frac;
+                    let window = 0.5 * (1.0 + (std::f64::consts::PI * x / POLYPHASE_HALF_WIDTH as f64).cos());
+                    sinc(x) * window
+                })
+                .collect();
+            let sum: f64 = row.iter().sum();
+            if sum.abs() > 1e-12 {
+                for w in row.iter_mut() {
+                    *w /= sum;
+                }
+            }
+            row
+        })
+        .collect()
+}
+
+/// Resample a single-channel `f64` signal from `from_rate` to `to_rate`
+/// using the given interpolation mode. For destination index `i`, the
+/// source position is `p = i * from_rate / to_rate`; out-of-range source
+/// indices are clamped to the signal edges.
+pub fn resample(samples: &[f64], from_rate: u32, to_rate: u32, mode: InterpolationMode) -> Vec<f64> {
+    if samples.is_empty() || from_rate == to_rate {
+        return samples.to_vec();
+    }
+
+    let ratio = from_rate as f64 / to_rate as f64;
+    let out_len = ((samples.len() as f64) * to_rate as f64 / from_rate as f64).round() as usize;
+    let bank = matches!(mode, InterpolationMode::Polyphase).then(polyphase_filter_bank);
+
+    (0..out_len)
+        .map(|i| {
+            let p = i as f64 * ratio;
+            let n = p.floor() as isize;
+            let t = p - n as f64;
+
+            match mode {
+                InterpolationMode::Nearest => sample_at(samples, p.round() as isize),
+                InterpolationMode::Linear => {
+                    let y0 = sample_at(samples, n);
+                    let y1 = sample_at(samples, n + 1);
+                    y0 * (1.0 - t) + y1 * t
+                }
+                InterpolationMode::Cosine => {
+                    let y0 = sample_at(samples, n);
+                    let y1 = sample_at(samples, n + 1);
+                    let m = (1.0 - (t * std::f64::consts::PI).cos()) / 2.0;
+                    y0 * (1.0 - m) + y1 * m
+                }
+                InterpolationMode::Cubic => {
+                    let y0 = sample_at(samples, n - 1);
+                    let y1 = sample_at(samples, n);
+                    let y2 = sample_at(samples, n + 1);
+                    let y3 = sample_at(samples, n + 2);
+                    let a0 = -0.5 * y0 + 1.5 * y1 - 1.5 * y2 + 0.5 * y3;
+                    let a1 = y0 - 2.5 * y1 + 2.0 * y2 - 0.5 * y3;
+                    let a2 = -0.5 * y0 + 0.5 * y2;
+                    let a3 = y1;
+                    ((a0 * t + a1) * t + a2) * t + a3
+                }
+                InterpolationMode::Polyphase => {
+                    let weights = &bank.as_ref().unwrap()[(t * POLYPHASE_SUBPHASES as f64).round() as usize];
+                    weights
+                        .iter()
+                        .enumerate()
+                        .map(|(k, &w)| sample_at(samples, n - POLYPHASE_HALF_WIDTH + k as isize) * w)
+                        .sum()
+                }
+            }
+        })
+        .collect()
+}
+
+/// Resample every channel of a (channels x samples) buffer from `in_rate`
+/// to `out_rate` using a windowed-sinc polyphase filter.
+pub fn resample_sinc(input: ArrayView2<f32>, in_rate: u32, out_rate: u32) -> Array2<f32> {
+    if in_rate == out_rate {
+        return input.to_owned();
+    }
+
+    let resampler = SincResampler::new(in_rate, out_rate);
+    let channels = input.shape()[0];
+
+    let converted: Vec<Vec<f32>> = (0..channels)
+        .map(|ch| resampler.process(&input.row(ch).to_vec()))
+        .collect();
+
+    let out_len = converted.first().map(|c| c.len()).unwrap_or(0);
+    let mut output = Array2::<f32>::zeros((channels, out_len));
+    for (ch, samples) in converted.iter().enumerate() {
+        for (i, &s) in samples.iter().enumerate() {
+            output[[ch, i]] = s;
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identity_ratio_is_passthrough() {
+        let resampler = SincResampler::new(44100, 44100);
+        let input = vec![0.1, 0.2, -0.3, 0.4];
+        assert_eq!(resampler.process(&input), input);
+    }
+
+    #[test]
+    fn output_length_matches_ratio() {
+        let resampler = SincResampler::new(44100, 48000);
+        let input = vec![0.0f32; 4410];
+        let output = resampler.process(&input);
+        assert_eq!(output.len(), 4410 * 48000 / 44100);
+    }
+
+    #[test]
+    fn upsampled_dc_signal_stays_near_constant() {
+        let resampler = SincResampler::new(8000, 16000);
+        let input = vec![1.0f32; 64];
+        let output = resampler.process(&input);
+        for &s in output.iter().skip(32).take(16) {
+            assert!((s - 1.0).abs() < 0.05, "expected ~1.0, got {}", s);
+        }
+    }
+
+    #[test]
+    fn resample_identity_ratio_is_passthrough() {
+        let input = vec![0.1, 0.2, -0.3, 0.4];
+        for mode in [
+            InterpolationMode::Nearest,
+            InterpolationMode::Linear,
+            InterpolationMode::Cosine,
+            InterpolationMode::Cubic,
+            InterpolationMode::Polyphase,
+        ] {
+            assert_eq!(resample(&input, 44100, 44100, mode), input);
+        }
+    }
+
+    #[test]
+    fn resample_output_length_matches_ratio() {
+        let input = vec![0.0; 4410];
+        let output = resample(&input, 44100, 48000, InterpolationMode::Linear);
+        assert_eq!(output.len(), 4410 * 48000 / 44100);
+    }
+
+    #[test]
+    fn resample_dc_signal_stays_constant_for_every_mode() {
+        let input = vec![1.0; 64];
+        for mode in [
+            InterpolationMode::Nearest,
+            InterpolationMode::Linear,
+            InterpolationMode::Cosine,
+            InterpolationMode::Cubic,
+            InterpolationMode::Polyphase,
+        ] {
+            let output = resample(&input, 8000, 16000, mode);
+            for &s in output.iter().skip(16).take(16) {
+                assert!((s - 1.0).abs() < 1e-6, "mode {:?}: expected ~1.0, got {}", mode, s);
+            }
+        }
+    }
+
+    #[test]
+    fn resample_from_str_parses_known_modes() {
+        assert_eq!("linear".parse::<InterpolationMode>(), Ok(InterpolationMode::Linear));
+        assert_eq!("Polyphase".parse::<InterpolationMode>(), Ok(InterpolationMode::Polyphase));
+        assert!("bogus".parse::<InterpolationMode>().is_err());
+    }
+}